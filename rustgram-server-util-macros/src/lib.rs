@@ -1,8 +1,8 @@
-#![allow(clippy::explicit_counter_loop)]
+#![allow(clippy::explicit_counter_loop, clippy::tabs_in_doc_comments)]
 
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{Fields, Ident, Type, TypePath, TypeReference};
+use syn::{Fields, Ident, LitInt, LitStr, Type, TypePath, TypeReference};
 
 #[proc_macro_derive(MariaDb)]
 pub fn maria_db_impl(input: TokenStream) -> TokenStream
@@ -92,6 +92,263 @@ pub fn sqlite_impl(input: TokenStream) -> TokenStream
 	expand.into()
 }
 
+/**
+# Error codes with catalog
+
+Implements ServerErrorCodes and ErrorCodeCatalog for an enum.
+The code is required for every variant, the http status and the msg are only used for the catalog.
+
+````ignore
+#[derive(ServerErrorCodes)]
+pub enum AppErrorCodes
+{
+	#[server_error(code = 1000, status = 400, msg = "User not found")]
+	UserNotFound,
+	#[server_error(code = 1001)]
+	UserExists,
+}
+````
+ */
+#[proc_macro_derive(ServerErrorCodes, attributes(server_error))]
+pub fn server_error_codes_impl(input: TokenStream) -> TokenStream
+{
+	let ast: syn::DeriveInput = syn::parse(input).unwrap();
+
+	let enum_name = ast.ident.clone();
+
+	let variants = match ast.data {
+		syn::Data::Enum(e) => e.variants,
+		_ => panic!("Only enums are supported"),
+	};
+
+	let mut codes = Vec::with_capacity(variants.len());
+	let mut match_arms = Vec::with_capacity(variants.len());
+	let mut catalog = Vec::with_capacity(variants.len());
+
+	for variant in variants {
+		let variant_ident = variant.ident;
+		let variant_name = variant_ident.to_string();
+
+		let mut code: Option<u32> = None;
+		let mut status = quote! { None };
+		let mut msg = quote! { None };
+
+		for attr in variant
+			.attrs
+			.iter()
+			.filter(|a| a.path().is_ident("server_error"))
+		{
+			attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("code") {
+					let v: LitInt = meta.value()?.parse()?;
+					code = Some(v.base10_parse()?);
+				} else if meta.path.is_ident("status") {
+					let v: LitInt = meta.value()?.parse()?;
+					let v: u16 = v.base10_parse()?;
+					status = quote! { Some(#v) };
+				} else if meta.path.is_ident("msg") {
+					let v: LitStr = meta.value()?.parse()?;
+					msg = quote! { Some(#v) };
+				} else {
+					return Err(meta.error("unsupported server_error property"));
+				}
+
+				Ok(())
+			})
+			.unwrap();
+		}
+
+		let code = match code {
+			Some(c) => c,
+			None => {
+				panic!(
+					"Missing #[server_error(code = ...)] for {}::{}",
+					enum_name, variant_name
+				)
+			},
+		};
+
+		if codes.contains(&code) {
+			panic!("Duplicate error code {} in {} at {}", code, enum_name, variant_name);
+		}
+
+		codes.push(code);
+
+		match_arms.push(quote! {
+			Self::#variant_ident { .. } => #code,
+		});
+
+		catalog.push(quote! {
+			rustgram_server_util::error::ErrorCodeInfo {
+				code: #code,
+				name: #variant_name,
+				status: #status,
+				msg: #msg,
+			},
+		});
+	}
+
+	let expand = quote! {
+		impl rustgram_server_util::error::ServerErrorCodes for #enum_name
+		{
+			fn get_int_code(&self) -> u32
+			{
+				match self {
+					#(#match_arms) *
+				}
+			}
+		}
+
+		impl rustgram_server_util::error::ErrorCodeCatalog for #enum_name
+		{
+			fn catalog() -> Vec<rustgram_server_util::error::ErrorCodeInfo>
+			{
+				vec![
+					#(#catalog) *
+				]
+			}
+		}
+	};
+
+	expand.into()
+}
+
+/**
+# Validation of the input
+
+Implements the Validate trait. Every failed rule is added as field error.
+
+Rules:
+- `length(min = .., max = ..)` for strings (chars) and vecs
+- `range(min = .., max = ..)` for numbers
+- `regex = ".."`
+- `email`
+- `uuid` with the id format check
+
+````ignore
+#[derive(Deserialize, Validate)]
+pub struct RegisterInput
+{
+	#[validate(length(min = 3, max = 20), regex = "^[a-z0-9_]+$")]
+	name: String,
+	#[validate(email)]
+	email: String,
+	#[validate(range(max = 150))]
+	age: Option<u32>,
+}
+````
+ */
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn validate_impl(input: TokenStream) -> TokenStream
+{
+	let ast: syn::DeriveInput = syn::parse(input).unwrap();
+
+	let struct_name = ast.ident.clone();
+
+	let fields = match ast.data {
+		syn::Data::Struct(syn::DataStruct {
+			fields: Fields::Named(fields),
+			..
+		}) => fields.named,
+		_ => panic!("Only structs with named fields are supported"),
+	};
+
+	let mut checks = Vec::new();
+
+	for field in fields {
+		let field_ident = field.ident.unwrap();
+		let field_name = field_ident.to_string();
+		let field_name = field_name.trim_start_matches("r#");
+
+		for attr in field.attrs.iter().filter(|a| a.path().is_ident("validate")) {
+			let res = attr.parse_nested_meta(|meta| {
+				if meta.path.is_ident("length") || meta.path.is_ident("range") {
+					let mut min = quote! { None };
+					let mut max = quote! { None };
+					let is_length = meta.path.is_ident("length");
+
+					meta.parse_nested_meta(|bound| {
+						let v: syn::Expr = bound.value()?.parse()?;
+
+						let v = if is_length {
+							quote! { Some((#v) as usize) }
+						} else {
+							quote! { Some((#v) as f64) }
+						};
+
+						if bound.path.is_ident("min") {
+							min = v;
+						} else if bound.path.is_ident("max") {
+							max = v;
+						} else {
+							return Err(bound.error("only min and max are supported"));
+						}
+
+						Ok(())
+					})?;
+
+					if is_length {
+						checks.push(quote! {
+							errors.extend(rustgram_server_util::validate::check_length(#field_name, &self.#field_ident, #min, #max));
+						});
+					} else {
+						checks.push(quote! {
+							errors.extend(rustgram_server_util::validate::check_range(#field_name, &self.#field_ident, #min, #max));
+						});
+					}
+				} else if meta.path.is_ident("regex") {
+					let pattern: LitStr = meta.value()?.parse()?;
+
+					//a wrong pattern should fail at compile time and not at the first request
+					if let Err(e) = regex::Regex::new(&pattern.value()) {
+						return Err(syn::Error::new(pattern.span(), format!("invalid regex: {}", e)));
+					}
+
+					checks.push(quote! {
+						{
+							static REGEX: std::sync::OnceLock<rustgram_server_util::validate::Regex> = std::sync::OnceLock::new();
+
+							errors.extend(rustgram_server_util::validate::check_regex(#field_name, &self.#field_ident, &REGEX, #pattern));
+						}
+					});
+				} else if meta.path.is_ident("email") {
+					checks.push(quote! {
+						errors.extend(rustgram_server_util::validate::check_email(#field_name, &self.#field_ident));
+					});
+				} else if meta.path.is_ident("uuid") {
+					checks.push(quote! {
+						errors.extend(rustgram_server_util::validate::check_uuid(#field_name, &self.#field_ident));
+					});
+				} else {
+					return Err(meta.error("unsupported validate rule"));
+				}
+
+				Ok(())
+			});
+
+			if let Err(e) = res {
+				return e.to_compile_error().into();
+			}
+		}
+	}
+
+	let expand = quote! {
+		impl rustgram_server_util::validate::Validate for #struct_name
+		{
+			fn validation_errors(&self) -> Vec<rustgram_server_util::error::FieldError>
+			{
+				let mut errors = Vec::new();
+
+				#(#checks) *
+
+				errors
+			}
+		}
+	};
+
+	expand.into()
+}
+
 fn get_struct_properties(input: TokenStream) -> (Ident, Vec<(Ident, Type)>)
 {
 	let ast: syn::DeriveInput = syn::parse(input).unwrap();
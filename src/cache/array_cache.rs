@@ -1,28 +1,45 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use tokio::sync::RwLock;
+use tokio::time::Instant;
 
-use crate::cache::Cache;
+use crate::cache::{Cache, Lock};
 use crate::get_time_in_sec;
 use crate::res::AppRes;
 
+const COUNTER_CLEANUP_LEN: usize = 1000;
+const LOCK_CLEANUP_LEN: usize = 1000;
+
 struct CacheData<T: 'static + Clone>
 {
 	value: T,
 	ttl: usize,
 }
 
+struct LockData
+{
+	token: String,
+	expire: Instant,
+}
+
 /**
 # Simple Array Cache with Multithreaded support
 
 https://stackoverflow.com/questions/27791532/how-do-i-create-a-global-mutable-singleton
 with RwLock instead of Mutex
+
+Counters from incr and decr are stored apart from the values, so a counter can't be read with get.
+
+Locks are only valid in this process.
  */
 pub struct ArrayCache<T: 'static + Clone>
 {
 	//https://docs.rs/tokio/latest/tokio/sync/struct.RwLock.html
 	cache: RwLock<HashMap<String, CacheData<T>>>,
+	counters: RwLock<HashMap<String, CacheData<i64>>>,
+	locks: RwLock<HashMap<String, LockData>>,
 }
 
 impl<T: 'static + Clone> ArrayCache<T>
@@ -31,6 +48,15 @@ impl<T: 'static + Clone> ArrayCache<T>
 	{
 		Self::default()
 	}
+
+	/**
+	Removes every entry from the cache
+	 */
+	pub async fn clear(&self)
+	{
+		self.cache.write().await.clear();
+		self.counters.write().await.clear();
+	}
 }
 
 impl<T: 'static + Clone> Default for ArrayCache<T>
@@ -39,6 +65,8 @@ impl<T: 'static + Clone> Default for ArrayCache<T>
 	{
 		Self {
 			cache: RwLock::new(HashMap::<String, CacheData<T>>::new()),
+			counters: RwLock::new(HashMap::<String, CacheData<i64>>::new()),
+			locks: RwLock::new(HashMap::<String, LockData>::new()),
 		}
 	}
 }
@@ -62,6 +90,44 @@ impl<T: 'static + Clone + Send + Sync> Cache<T> for ArrayCache<T>
 		}
 	}
 
+	async fn get_and_touch(&self, key: &str, ttl: usize) -> AppRes<Option<T>>
+	{
+		let now = get_time_in_sec()? as usize;
+
+		match self.cache.write().await.get_mut(key) {
+			Some(v) if v.ttl >= now => {
+				v.ttl = now + ttl;
+
+				Ok(Some(v.value.clone()))
+			},
+			_ => Ok(None),
+		}
+	}
+
+	async fn ttl(&self, key: &str) -> AppRes<Option<Duration>>
+	{
+		let now = get_time_in_sec()? as usize;
+
+		match self.cache.read().await.get(key) {
+			Some(v) if v.ttl >= now => Ok(Some(Duration::from_secs((v.ttl - now) as u64))),
+			_ => Ok(None),
+		}
+	}
+
+	async fn expire(&self, key: &str, ttl: usize) -> AppRes<bool>
+	{
+		let now = get_time_in_sec()? as usize;
+
+		match self.cache.write().await.get_mut(key) {
+			Some(v) if v.ttl >= now => {
+				v.ttl = now + ttl;
+
+				Ok(true)
+			},
+			_ => Ok(false),
+		}
+	}
+
 	async fn add(&self, key: String, value: T, ttl: usize) -> AppRes<()>
 	{
 		let ttl = ttl + get_time_in_sec()? as usize;
@@ -80,6 +146,7 @@ impl<T: 'static + Clone + Send + Sync> Cache<T> for ArrayCache<T>
 	async fn delete(&self, key: &str) -> AppRes<()>
 	{
 		self.cache.write().await.remove(key);
+		self.counters.write().await.remove(key);
 
 		Ok(())
 	}
@@ -92,6 +159,98 @@ impl<T: 'static + Clone + Send + Sync> Cache<T> for ArrayCache<T>
 			c.remove(*key);
 		}
 
+		let mut c = self.counters.write().await;
+
+		for key in keys {
+			c.remove(*key);
+		}
+
 		Ok(())
 	}
+
+	async fn incr(&self, key: &str, by: i64, ttl: usize) -> AppRes<i64>
+	{
+		let now = get_time_in_sec()? as usize;
+
+		let mut counters = self.counters.write().await;
+
+		if !counters.contains_key(key) && counters.len() >= COUNTER_CLEANUP_LEN {
+			//every window creates new keys, so remove the old counters from time to time
+			counters.retain(|_, c| c.ttl >= now);
+		}
+
+		let counter = counters.entry(key.to_string()).or_insert(CacheData {
+			value: 0,
+			ttl: now + ttl,
+		});
+
+		if counter.ttl < now {
+			counter.value = 0;
+			counter.ttl = now + ttl;
+		}
+
+		counter.value += by;
+
+		Ok(counter.value)
+	}
+}
+
+#[async_trait]
+impl<T: 'static + Clone + Send + Sync> Lock for ArrayCache<T>
+{
+	async fn acquire(&self, key: &str, token: &str, ttl: Duration) -> AppRes<bool>
+	{
+		let now = Instant::now();
+
+		let mut locks = self.locks.write().await;
+
+		if let Some(l) = locks.get(key) {
+			if l.expire > now {
+				return Ok(false);
+			}
+		} else if locks.len() >= LOCK_CLEANUP_LEN {
+			//locks which were not released (e.g. a guard dropped without a runtime) are only replaced by the same key
+			locks.retain(|_, l| l.expire > now);
+		}
+
+		locks.insert(
+			key.to_string(),
+			LockData {
+				token: token.to_string(),
+				expire: now + ttl,
+			},
+		);
+
+		Ok(true)
+	}
+
+	async fn renew(&self, key: &str, token: &str, ttl: Duration) -> AppRes<bool>
+	{
+		let now = Instant::now();
+
+		match self.locks.write().await.get_mut(key) {
+			Some(l) if l.token == token && l.expire > now => {
+				l.expire = now + ttl;
+
+				Ok(true)
+			},
+			_ => Ok(false),
+		}
+	}
+
+	async fn release(&self, key: &str, token: &str) -> AppRes<bool>
+	{
+		let mut locks = self.locks.write().await;
+
+		match locks.get(key) {
+			Some(l) if l.token == token => {
+				let valid = l.expire > Instant::now();
+
+				locks.remove(key);
+
+				Ok(valid)
+			},
+			_ => Ok(false),
+		}
+	}
 }
@@ -0,0 +1,196 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::db::id_handling::create_id_v4;
+use crate::error::{server_err, CoreErrorCodes, ServerCoreError};
+use crate::res::AppRes;
+
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/**
+# Backend for locks
+
+Every lock is identified by the key and owned by the holder of the token.
+Only the holder of the token can renew or release the lock.
+
+The defaults return an error, a lock must be atomic in the backend.
+ */
+#[async_trait]
+pub trait Lock: Send + Sync
+{
+	/**
+	Sets the lock if there is no lock for the key. Returns false if the key is already locked.
+	 */
+	async fn acquire(&self, _key: &str, _token: &str, _ttl: Duration) -> AppRes<bool>
+	{
+		Err(lock_unsupported())
+	}
+
+	async fn renew(&self, _key: &str, _token: &str, _ttl: Duration) -> AppRes<bool>
+	{
+		Err(lock_unsupported())
+	}
+
+	async fn release(&self, _key: &str, _token: &str) -> AppRes<bool>
+	{
+		Err(lock_unsupported())
+	}
+}
+
+fn lock_unsupported() -> ServerCoreError
+{
+	server_err(
+		500,
+		CoreErrorCodes::CacheUnsupported,
+		"Locks are not supported by this cache",
+	)
+}
+
+/**
+# A held lock
+
+The lock is released when the guard is dropped. Use release to wait for the release and to see the result.
+
+The lock is also released by the backend after the ttl, even if the guard is still alive.
+Use auto_renew for jobs which may take longer than the ttl.
+
+````ignore
+async fn cron_job(cache: Arc<RedisCache<String>>)
+{
+	let guard = match LockGuard::try_lock(cache, "cron_job", Duration::from_secs(30)).await.unwrap() {
+		Some(g) => g.auto_renew(),
+		//another node is already running this job
+		None => return,
+	};
+
+	//do the job
+
+	guard.release().await.unwrap();
+}
+````
+ */
+pub struct LockGuard
+{
+	lock: Arc<dyn Lock>,
+	key: String,
+	token: String,
+	ttl: Duration,
+	renew_task: Option<JoinHandle<()>>,
+	released: bool,
+}
+
+impl LockGuard
+{
+	pub async fn try_lock(lock: Arc<dyn Lock>, key: &str, ttl: Duration) -> AppRes<Option<Self>>
+	{
+		let key = format!("lock_{}", key);
+		let token = create_id_v4();
+
+		if !lock.acquire(&key, &token, ttl).await? {
+			return Ok(None);
+		}
+
+		Ok(Some(Self {
+			lock,
+			key,
+			token,
+			ttl,
+			renew_task: None,
+			released: false,
+		}))
+	}
+
+	/**
+	Tries to get the lock until the timeout is reached. Returns None if the lock is still held by someone else.
+	 */
+	pub async fn lock_with_wait(lock: Arc<dyn Lock>, key: &str, ttl: Duration, timeout: Duration) -> AppRes<Option<Self>>
+	{
+		let end = Instant::now() + timeout;
+
+		loop {
+			if let Some(g) = Self::try_lock(lock.clone(), key, ttl).await? {
+				return Ok(Some(g));
+			}
+
+			let now = Instant::now();
+
+			if now >= end {
+				return Ok(None);
+			}
+
+			tokio::time::sleep(LOCK_RETRY_INTERVAL.min(end - now)).await;
+		}
+	}
+
+	/**
+	Renews the lock every half ttl while the guard is alive.
+
+	The renewal stops if the lock was lost, e.g. because the backend was not reachable until the ttl ran out.
+	 */
+	pub fn auto_renew(mut self) -> Self
+	{
+		let lock = self.lock.clone();
+		let key = self.key.clone();
+		let token = self.token.clone();
+		let ttl = self.ttl;
+
+		self.renew_task = Some(tokio::spawn(async move {
+			loop {
+				tokio::time::sleep(ttl / 2).await;
+
+				match lock.renew(&key, &token, ttl).await {
+					Ok(true) => {},
+					_ => return,
+				}
+			}
+		}));
+
+		self
+	}
+
+	/**
+	Releases the lock. Returns false if the lock was already released by the backend because of the ttl.
+	 */
+	pub async fn release(mut self) -> AppRes<bool>
+	{
+		self.stop_renew();
+		self.released = true;
+
+		self.lock.release(&self.key, &self.token).await
+	}
+
+	fn stop_renew(&mut self)
+	{
+		if let Some(t) = self.renew_task.take() {
+			t.abort();
+		}
+	}
+}
+
+impl Drop for LockGuard
+{
+	fn drop(&mut self)
+	{
+		self.stop_renew();
+
+		if self.released {
+			return;
+		}
+
+		//without a runtime the lock is released by the backend after the ttl
+		if let Ok(handle) = Handle::try_current() {
+			let lock = self.lock.clone();
+			let key = std::mem::take(&mut self.key);
+			let token = std::mem::take(&mut self.token);
+
+			handle.spawn(async move {
+				let _ = lock.release(&key, &token).await;
+			});
+		}
+	}
+}
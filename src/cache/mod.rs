@@ -1,13 +1,26 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{server_err, CoreErrorCodes};
 use crate::res::AppRes;
 
 mod array_cache;
+mod lock;
+mod rate_limiter;
 mod redis_cache;
+#[cfg(feature = "sqlite")]
+mod sqlite_cache;
+mod tiered_cache;
 
 pub use array_cache::ArrayCache;
+pub use lock::{Lock, LockGuard};
+pub use rate_limiter::{RateLimitInfo, RateLimitStrategy, RateLimiter};
 pub use redis_cache::RedisCache;
+#[cfg(feature = "sqlite")]
+pub use sqlite_cache::SqliteCache;
+pub use tiered_cache::{TieredCache, TIERED_CACHE_CHANNEL};
 
 #[cfg(feature = "static_var")]
 pub use crate::static_var::cache::*;
@@ -19,9 +32,74 @@ pub trait Cache<T: 'static + Clone>: Send + Sync
 
 	async fn add(&self, key: String, value: T, ttl: usize) -> AppRes<()>;
 
+	/**
+	Gets the value and sets the ttl of the key to the new ttl (sliding expiration)
+
+	The default is get, add and get again and this is not atomic:
+	a delete or add of another task between the calls can bring an old value back or return the other value.
+	It is only safe for caches with a single writer, caches which are shared between nodes must implement it atomically.
+	 */
+	async fn get_and_touch(&self, key: &str, ttl: usize) -> AppRes<Option<T>>
+	{
+		let value = match self.get(key).await? {
+			Some(v) => v,
+			None => return Ok(None),
+		};
+
+		//T is not Send, so the value can't be kept over the await
+		self.add(key.to_string(), value, ttl).await?;
+
+		self.get(key).await
+	}
+
+	/**
+	The time until the key expires. None if the key not exists.
+
+	The default returns an error because the ttl can't be read with get.
+	 */
+	async fn ttl(&self, _key: &str) -> AppRes<Option<Duration>>
+	{
+		Err(server_err(
+			500,
+			CoreErrorCodes::CacheUnsupported,
+			"Ttl is not supported by this cache",
+		))
+	}
+
+	/**
+	Sets a new ttl for the key. Returns false if the key not exists.
+
+	The default uses get_and_touch, so it is not atomic either.
+	 */
+	async fn expire(&self, key: &str, ttl: usize) -> AppRes<bool>
+	{
+		Ok(self.get_and_touch(key, ttl).await?.is_some())
+	}
+
 	async fn delete(&self, key: &str) -> AppRes<()>;
 
 	async fn delete_multiple(&self, keys: &[&str]) -> AppRes<()>;
+
+	/**
+	Increments the counter of the key by `by` and returns the new value.
+
+	A new counter starts at 0 and expires after ttl sec. The ttl is not refreshed by later increments.
+
+	The default returns an error, counters must be atomic and this can't be done with get and add.
+	 */
+	async fn incr(&self, _key: &str, _by: i64, _ttl: usize) -> AppRes<i64>
+	{
+		Err(server_err(
+			500,
+			CoreErrorCodes::CacheUnsupported,
+			"Counters are not supported by this cache",
+		))
+	}
+
+	async fn decr(&self, key: &str, by: i64, ttl: usize) -> AppRes<i64>
+	{
+		self.incr(key, -by, ttl).await
+	}
 }
 
 #[derive(Serialize, Deserialize)]
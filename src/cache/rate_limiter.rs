@@ -0,0 +1,175 @@
+use crate::cache::Cache;
+use crate::error::{server_err_owned, CoreErrorCodes, ServerCoreError};
+use crate::get_time_in_sec;
+use crate::res::AppRes;
+
+/**
+Blocked hits are not counted by both strategies, otherwise a client which keeps trying is blocked forever with the sliding window.
+ */
+pub enum RateLimitStrategy
+{
+	/// Counts the hits per fixed window, e.g. from 12:00:00 to 12:01:00.
+	FixedWindow,
+	/// Weights the hits of the last window by the time that has passed in the current window.
+	/// This prevents bursts at the window borders.
+	SlidingWindow,
+}
+
+#[derive(Debug)]
+pub struct RateLimitInfo
+{
+	pub allowed: bool,
+	pub limit: i64,
+	pub remaining: i64,
+	/// Unix time in sec when the current window ends
+	pub reset: u64,
+}
+
+impl RateLimitInfo
+{
+	/**
+	Returns the info back or a 429 error if the limit is reached
+	 */
+	pub fn into_result(self) -> AppRes<Self>
+	{
+		if self.allowed {
+			return Ok(self);
+		}
+
+		let retry_after = self
+			.reset
+			.saturating_sub(get_time_in_sec().unwrap_or(self.reset));
+
+		Err(rate_limit_err(retry_after))
+	}
+}
+
+fn rate_limit_err(retry_after: u64) -> ServerCoreError
+{
+	server_err_owned(
+		429,
+		CoreErrorCodes::RateLimitExceeded,
+		format!("Too many requests. Try again in {} sec", retry_after),
+		None,
+	)
+}
+
+/**
+# Rate limiter based on the counters of a cache
+
+The key should identify the client and the action, e.g. `login_<user_id>`.
+
+````ignore
+const LOGIN_LIMIT: RateLimiter = RateLimiter::new(5, 60, RateLimitStrategy::FixedWindow);
+
+async fn login(req: Request) -> JRes<ServerSuccessOutput>
+{
+	LOGIN_LIMIT.check(cache::cache(), "login_123").await?;
+
+	echo_success()
+}
+````
+ */
+pub struct RateLimiter
+{
+	limit: i64,
+	window: u64,
+	strategy: RateLimitStrategy,
+}
+
+impl RateLimiter
+{
+	/**
+	`limit` max hits per window
+
+	`window` the window size in sec, must not be 0
+	 */
+	pub const fn new(limit: i64, window: u64, strategy: RateLimitStrategy) -> Self
+	{
+		assert!(window > 0, "The rate limit window must not be 0");
+
+		Self {
+			limit,
+			window,
+			strategy,
+		}
+	}
+
+	/**
+	Counts the hit and returns the quota. An exceeded limit is returned as info with `allowed = false`.
+	 */
+	pub async fn hit<T: 'static + Clone, C: Cache<T> + ?Sized>(&self, cache: &C, key: &str) -> AppRes<RateLimitInfo>
+	{
+		self.hit_at(cache, key, get_time_in_sec()?).await
+	}
+
+	/**
+	Counts the hit at the unix time in sec. Useful for tests which must not cross a window border.
+	 */
+	pub async fn hit_at<T: 'static + Clone, C: Cache<T> + ?Sized>(&self, cache: &C, key: &str, now: u64) -> AppRes<RateLimitInfo>
+	{
+		let window_start = now - now % self.window;
+		let reset = window_start + self.window;
+
+		let key_current = format!("rate_limit_{}_{}", key, window_start);
+
+		match self.strategy {
+			RateLimitStrategy::FixedWindow => {
+				let count = cache.incr(&key_current, 1, self.window as usize).await?;
+
+				let allowed = count <= self.limit;
+
+				if !allowed {
+					cache.decr(&key_current, 1, self.window as usize).await?;
+				}
+
+				Ok(RateLimitInfo {
+					allowed,
+					limit: self.limit,
+					remaining: (self.limit - count).max(0),
+					reset,
+				})
+			},
+			RateLimitStrategy::SlidingWindow => {
+				//the current counter is needed as previous counter in the next window
+				let ttl = (self.window * 2) as usize;
+
+				//no previous window before the unix epoch + window
+				let prev = match window_start.checked_sub(self.window) {
+					Some(prev_start) => {
+						cache
+							.incr(&format!("rate_limit_{}_{}", key, prev_start), 0, ttl)
+							.await?
+					},
+					None => 0,
+				};
+				let count = cache.incr(&key_current, 1, ttl).await?;
+
+				let elapsed = now - window_start;
+				let weight = (self.window - elapsed) as f64 / self.window as f64;
+				let estimated = (prev as f64 * weight).floor() as i64 + count;
+
+				let allowed = estimated <= self.limit;
+
+				if !allowed {
+					cache.decr(&key_current, 1, ttl).await?;
+				}
+
+				Ok(RateLimitInfo {
+					allowed,
+					limit: self.limit,
+					remaining: (self.limit - estimated).max(0),
+					reset,
+				})
+			},
+		}
+	}
+
+	/**
+	Counts the hit and returns a 429 error if the limit is exceeded
+	 */
+	pub async fn check<T: 'static + Clone, C: Cache<T> + ?Sized>(&self, cache: &C, key: &str) -> AppRes<RateLimitInfo>
+	{
+		self.hit(cache, key).await?.into_result()
+	}
+}
@@ -1,23 +1,43 @@
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use redis::aio::Connection;
-use redis::{AsyncCommands, Client, FromRedisValue, RedisError, ToRedisArgs};
+use redis::{AsyncCommands, Client, FromRedisValue, RedisError, Script, ToRedisArgs};
 
-use crate::cache::Cache;
-use crate::error::{CoreErrorCodes, ServerCoreError, ServerErrorConstructor};
+use crate::cache::{Cache, Lock};
+use crate::error::ServerCoreError;
 use crate::res::AppRes;
 
-fn wrap_redis_error(e: RedisError) -> ServerCoreError
+pub(crate) fn wrap_redis_error(e: RedisError) -> ServerCoreError
 {
-	ServerCoreError::new_msg_and_debug(
-		400,
-		CoreErrorCodes::RedisError,
-		"Error with redis cache",
-		Some(format!("Error with redis cache: {:?}", e)),
-	)
+	e.into()
 }
 
+//set the ttl only for new counters, like the array cache
+const INCR_SCRIPT: &str = r#"
+local v = redis.call('INCRBY', KEYS[1], ARGV[1])
+if redis.call('TTL', KEYS[1]) == -1 then
+	redis.call('EXPIRE', KEYS[1], ARGV[2])
+end
+return v
+"#;
+
+//only the owner of the token can release or renew the lock
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+	return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+const RENEW_LOCK_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+	return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+
 pub struct RedisCache<T: 'static + Clone>
 {
 	p: PhantomData<T>,
@@ -63,18 +83,52 @@ impl<T: 'static + Clone + Send + Sync + FromRedisValue + ToRedisArgs> Cache<T> f
 			.atomic()
 			.set(&key, value)
 			.expire(key, ttl)
-			.query_async(&mut con)
+			.query_async::<_, ()>(&mut con)
 			.await
 			.map_err(wrap_redis_error)?;
 
 		Ok(())
 	}
 
+	async fn get_and_touch(&self, key: &str, ttl: usize) -> AppRes<Option<T>>
+	{
+		let mut con = self.get_con().await?;
+
+		redis::cmd("GETEX")
+			.arg(key)
+			.arg("EX")
+			.arg(ttl)
+			.query_async(&mut con)
+			.await
+			.map_err(wrap_redis_error)
+	}
+
+	async fn ttl(&self, key: &str) -> AppRes<Option<Duration>>
+	{
+		let mut con = self.get_con().await?;
+
+		let ttl: i64 = con.ttl(key).await.map_err(wrap_redis_error)?;
+
+		//-2 for not existing keys and -1 for keys without ttl, but every key is set with a ttl
+		if ttl < 0 {
+			return Ok(None);
+		}
+
+		Ok(Some(Duration::from_secs(ttl as u64)))
+	}
+
+	async fn expire(&self, key: &str, ttl: usize) -> AppRes<bool>
+	{
+		let mut con = self.get_con().await?;
+
+		con.expire(key, ttl).await.map_err(wrap_redis_error)
+	}
+
 	async fn delete(&self, key: &str) -> AppRes<()>
 	{
 		let mut con = self.get_con().await?;
 
-		con.del(key).await.map_err(wrap_redis_error)?;
+		con.del::<_, ()>(key).await.map_err(wrap_redis_error)?;
 
 		Ok(())
 	}
@@ -83,8 +137,71 @@ impl<T: 'static + Clone + Send + Sync + FromRedisValue + ToRedisArgs> Cache<T> f
 	{
 		let mut con = self.get_con().await?;
 
-		con.del(keys).await.map_err(wrap_redis_error)?;
+		con.del::<_, ()>(keys).await.map_err(wrap_redis_error)?;
 
 		Ok(())
 	}
+
+	async fn incr(&self, key: &str, by: i64, ttl: usize) -> AppRes<i64>
+	{
+		let mut con = self.get_con().await?;
+
+		Script::new(INCR_SCRIPT)
+			.key(key)
+			.arg(by)
+			.arg(ttl)
+			.invoke_async(&mut con)
+			.await
+			.map_err(wrap_redis_error)
+	}
+}
+
+#[async_trait]
+impl<T: 'static + Clone + Send + Sync> Lock for RedisCache<T>
+{
+	async fn acquire(&self, key: &str, token: &str, ttl: Duration) -> AppRes<bool>
+	{
+		let mut con = self.get_con().await?;
+
+		let res: Option<String> = redis::cmd("SET")
+			.arg(key)
+			.arg(token)
+			.arg("NX")
+			.arg("PX")
+			.arg(ttl.as_millis() as u64)
+			.query_async(&mut con)
+			.await
+			.map_err(wrap_redis_error)?;
+
+		Ok(res.is_some())
+	}
+
+	async fn renew(&self, key: &str, token: &str, ttl: Duration) -> AppRes<bool>
+	{
+		let mut con = self.get_con().await?;
+
+		let res: i32 = Script::new(RENEW_LOCK_SCRIPT)
+			.key(key)
+			.arg(token)
+			.arg(ttl.as_millis() as u64)
+			.invoke_async(&mut con)
+			.await
+			.map_err(wrap_redis_error)?;
+
+		Ok(res == 1)
+	}
+
+	async fn release(&self, key: &str, token: &str) -> AppRes<bool>
+	{
+		let mut con = self.get_con().await?;
+
+		let res: i32 = Script::new(RELEASE_LOCK_SCRIPT)
+			.key(key)
+			.arg(token)
+			.invoke_async(&mut con)
+			.await
+			.map_err(wrap_redis_error)?;
+
+		Ok(res == 1)
+	}
 }
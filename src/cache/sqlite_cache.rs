@@ -0,0 +1,224 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::cache::Cache;
+use crate::db::{get_in, Db, I64Entity, StringEntity};
+use crate::input_helper::{bytes_to_json, json_to_string};
+use crate::res::AppRes;
+use crate::{get_time_in_sec, set_params};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/**
+# Persistent cache in a sqlite table
+
+For single node deployments without redis. The values are stored as json in the app db,
+so the cache uses the same connection pool as the app and there is only one writer for the db file.
+
+The ttl works like in the ArrayCache. Expired values are ignored when reading
+and are removed by a background task, so this cache must be created inside a tokio runtime.
+ */
+pub struct SqliteCache<T: 'static + Clone>
+{
+	p: PhantomData<T>,
+	db: &'static Db,
+	//stops the cleanup task when the cache is dropped
+	_stop: oneshot::Sender<()>,
+}
+
+impl<T: 'static + Clone> SqliteCache<T>
+{
+	/**
+	Creates the cache tables in the app db if they not exist.
+
+	With the static_var feature use the global db: `SqliteCache::new(db::db())`
+	 */
+	pub async fn new(db: &'static Db) -> AppRes<Self>
+	{
+		//language=SQL
+		db.exec_non_param("CREATE TABLE IF NOT EXISTS rustgram_cache (cache_key TEXT PRIMARY KEY, value TEXT NOT NULL, ttl INTEGER NOT NULL)")
+			.await?;
+
+		//language=SQL
+		db.exec_non_param(
+			"CREATE TABLE IF NOT EXISTS rustgram_cache_counter (cache_key TEXT PRIMARY KEY, value INTEGER NOT NULL, ttl INTEGER NOT NULL)",
+		)
+		.await?;
+
+		let (stop_tx, stop_rx) = oneshot::channel();
+
+		tokio::spawn(remove_expired(db, stop_rx));
+
+		Ok(Self {
+			p: Default::default(),
+			db,
+			_stop: stop_tx,
+		})
+	}
+}
+
+async fn remove_expired(db: &'static Db, mut stop: oneshot::Receiver<()>)
+{
+	loop {
+		tokio::select! {
+			_ = &mut stop => return,
+			_ = tokio::time::sleep(CLEANUP_INTERVAL) => {},
+		}
+
+		let now = match get_time_in_sec() {
+			Ok(t) => t as i64,
+			Err(_e) => continue,
+		};
+
+		//errors are ignored here, the next run will try it again
+
+		//language=SQL
+		let _ = db
+			.exec("DELETE FROM rustgram_cache WHERE ttl < ?", set_params!(now))
+			.await;
+
+		//language=SQL
+		let _ = db
+			.exec("DELETE FROM rustgram_cache_counter WHERE ttl < ?", set_params!(now))
+			.await;
+	}
+}
+
+#[async_trait]
+impl<T: 'static + Clone + Send + Sync + Serialize + DeserializeOwned> Cache<T> for SqliteCache<T>
+{
+	async fn get(&self, key: &str) -> AppRes<Option<T>>
+	{
+		let now = get_time_in_sec()? as i64;
+
+		//language=SQL
+		let sql = "SELECT value FROM rustgram_cache WHERE cache_key = ? AND ttl >= ?";
+
+		let value: Option<StringEntity> = self
+			.db
+			.query_first(sql, set_params!(key.to_string(), now))
+			.await?;
+
+		match value {
+			Some(v) => Ok(Some(bytes_to_json(v.0.as_bytes())?)),
+			None => Ok(None),
+		}
+	}
+
+	async fn add(&self, key: String, value: T, ttl: usize) -> AppRes<()>
+	{
+		let ttl = ttl as i64 + get_time_in_sec()? as i64;
+		let value = json_to_string(&value)?;
+
+		//language=SQL
+		let sql = "INSERT OR REPLACE INTO rustgram_cache (cache_key, value, ttl) VALUES (?,?,?)";
+
+		self.db.exec(sql, set_params!(key, value, ttl)).await
+	}
+
+	async fn get_and_touch(&self, key: &str, ttl: usize) -> AppRes<Option<T>>
+	{
+		let now = get_time_in_sec()? as i64;
+		let ttl = ttl as i64 + now;
+
+		//language=SQL
+		let sql = "UPDATE rustgram_cache SET ttl = ? WHERE cache_key = ? AND ttl >= ? RETURNING value";
+
+		let value: Option<StringEntity> = self
+			.db
+			.query_first(sql, set_params!(ttl, key.to_string(), now))
+			.await?;
+
+		match value {
+			Some(v) => Ok(Some(bytes_to_json(v.0.as_bytes())?)),
+			None => Ok(None),
+		}
+	}
+
+	async fn ttl(&self, key: &str) -> AppRes<Option<Duration>>
+	{
+		let now = get_time_in_sec()? as i64;
+
+		//language=SQL
+		let sql = "SELECT ttl FROM rustgram_cache WHERE cache_key = ? AND ttl >= ?";
+
+		let ttl: Option<I64Entity> = self
+			.db
+			.query_first(sql, set_params!(key.to_string(), now))
+			.await?;
+
+		Ok(ttl.map(|t| Duration::from_secs((t.0 - now) as u64)))
+	}
+
+	async fn expire(&self, key: &str, ttl: usize) -> AppRes<bool>
+	{
+		let now = get_time_in_sec()? as i64;
+		let ttl = ttl as i64 + now;
+
+		//language=SQL
+		let sql = "UPDATE rustgram_cache SET ttl = ? WHERE cache_key = ? AND ttl >= ? RETURNING cache_key";
+
+		let key: Option<StringEntity> = self
+			.db
+			.query_first(sql, set_params!(ttl, key.to_string(), now))
+			.await?;
+
+		Ok(key.is_some())
+	}
+
+	async fn delete(&self, key: &str) -> AppRes<()>
+	{
+		//language=SQL
+		let sql = "DELETE FROM rustgram_cache WHERE cache_key = ?";
+		self.db.exec(sql, set_params!(key.to_string())).await?;
+
+		//language=SQL
+		let sql = "DELETE FROM rustgram_cache_counter WHERE cache_key = ?";
+		self.db.exec(sql, set_params!(key.to_string())).await
+	}
+
+	async fn delete_multiple(&self, keys: &[&str]) -> AppRes<()>
+	{
+		if keys.is_empty() {
+			return Ok(());
+		}
+
+		let ins = get_in(keys);
+		let params: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+
+		//language=SQLx
+		let sql = format!("DELETE FROM rustgram_cache WHERE cache_key IN ({})", ins);
+		self.db.exec_string(sql, params.clone()).await?;
+
+		//language=SQLx
+		let sql = format!("DELETE FROM rustgram_cache_counter WHERE cache_key IN ({})", ins);
+		self.db.exec_string(sql, params).await
+	}
+
+	async fn incr(&self, key: &str, by: i64, ttl: usize) -> AppRes<i64>
+	{
+		let now = get_time_in_sec()? as i64;
+		let ttl = ttl as i64 + now;
+
+		//reset an expired counter in the same stmt to keep it atomic
+		//language=SQL
+		let sql = r"
+INSERT INTO rustgram_cache_counter (cache_key, value, ttl) VALUES (?,?,?)
+ON CONFLICT(cache_key) DO UPDATE SET
+	value = CASE WHEN ttl < ? THEN excluded.value ELSE value + excluded.value END,
+	ttl = CASE WHEN ttl < ? THEN excluded.ttl ELSE ttl END
+RETURNING value";
+
+		let value: Option<I64Entity> = self
+			.db
+			.query_first(sql, set_params!(key.to_string(), by, ttl, now, now))
+			.await?;
+
+		Ok(value.map(|v| v.0).unwrap_or(by))
+	}
+}
@@ -0,0 +1,273 @@
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use redis::{AsyncCommands, Client, FromRedisValue, ToRedisArgs};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::cache::redis_cache::wrap_redis_error;
+use crate::cache::{ArrayCache, Cache, Lock, RedisCache};
+use crate::db::id_handling::create_id;
+use crate::input_helper::{bytes_to_json, json_to_string};
+use crate::res::AppRes;
+
+pub const TIERED_CACHE_CHANNEL: &str = "rustgram_cache_invalidation";
+
+#[derive(Serialize, Deserialize)]
+struct InvalidationMsg
+{
+	node: String,
+	keys: Vec<String>,
+}
+
+/**
+# Two tier cache
+
+A local ArrayCache (L1) in front of a RedisCache (L2).
+
+Values are kept in the L1 cache for at most `l1_ttl` seconds and never longer than the remaining ttl in redis.
+Every add and delete is published over a redis pub/sub channel, so every other node removes its local copy of the key.
+
+Must be created inside a tokio runtime because the subscriber is spawned as a task.
+The task stops when the cache is dropped.
+ */
+pub struct TieredCache<T: 'static + Clone>
+{
+	l1: Arc<ArrayCache<T>>,
+	l2: RedisCache<T>,
+	l1_ttl: usize,
+	channel: String,
+	node: String,
+	//stops the subscriber task when the cache is dropped
+	_stop: oneshot::Sender<()>,
+}
+
+impl<T: 'static + Clone + Send + Sync> TieredCache<T>
+{
+	pub fn new(redis_url: &str, l1_ttl: usize, channel: &str) -> Self
+	{
+		let l1 = Arc::new(ArrayCache::new());
+		let node = create_id();
+
+		let client = Client::open(redis_url).unwrap();
+
+		let (stop_tx, stop_rx) = oneshot::channel();
+
+		tokio::spawn(listen_for_invalidation(
+			client,
+			channel.to_string(),
+			node.clone(),
+			Arc::downgrade(&l1),
+			stop_rx,
+		));
+
+		Self {
+			l1,
+			l2: RedisCache::new(redis_url),
+			l1_ttl,
+			channel: channel.to_string(),
+			node,
+			_stop: stop_tx,
+		}
+	}
+
+	async fn publish(&self, keys: Vec<String>) -> AppRes<()>
+	{
+		let msg = json_to_string(&InvalidationMsg {
+			node: self.node.clone(),
+			keys,
+		})?;
+
+		let mut con = self.l2.get_con().await?;
+
+		con.publish::<_, _, ()>(&self.channel, msg)
+			.await
+			.map_err(wrap_redis_error)
+	}
+}
+
+async fn listen_for_invalidation<T: 'static + Clone + Send + Sync>(
+	client: Client,
+	channel: String,
+	node: String,
+	l1: Weak<ArrayCache<T>>,
+	mut stop: oneshot::Receiver<()>,
+)
+{
+	loop {
+		tokio::select! {
+			//the sender is dropped with the cache, also on an idle channel without messages
+			_ = &mut stop => return,
+			_ = subscribe(&client, &channel, &node, &l1) => {},
+		}
+
+		//the connection was lost, so invalidation messages might be missed in the meantime
+		match l1.upgrade() {
+			Some(l1) => l1.clear().await,
+			None => return,
+		}
+
+		tokio::select! {
+			_ = &mut stop => return,
+			_ = tokio::time::sleep(Duration::from_secs(1)) => {},
+		}
+	}
+}
+
+async fn subscribe<T: 'static + Clone + Send + Sync>(client: &Client, channel: &str, node: &str, l1: &Weak<ArrayCache<T>>)
+{
+	let con = match client.get_async_connection().await {
+		Ok(c) => c,
+		Err(_e) => return,
+	};
+
+	let mut pubsub = con.into_pubsub();
+
+	if pubsub.subscribe(channel).await.is_err() {
+		return;
+	}
+
+	let mut stream = pubsub.on_message();
+
+	while let Some(msg) = stream.next().await {
+		let l1 = match l1.upgrade() {
+			Some(l1) => l1,
+			None => return,
+		};
+
+		let msg = match bytes_to_json::<InvalidationMsg>(msg.get_payload_bytes()) {
+			Ok(m) => m,
+			Err(_e) => continue,
+		};
+
+		if msg.node == node {
+			continue;
+		}
+
+		let keys: Vec<&str> = msg.keys.iter().map(|k| k.as_str()).collect();
+
+		//array cache delete never fails
+		let _ = l1.delete_multiple(&keys).await;
+	}
+}
+
+#[async_trait]
+impl<T: 'static + Clone + Send + Sync + FromRedisValue + ToRedisArgs> Cache<T> for TieredCache<T>
+{
+	async fn get(&self, key: &str) -> AppRes<Option<T>>
+	{
+		if let Some(v) = self.l1.get(key).await? {
+			return Ok(Some(v));
+		}
+
+		let mut con = self.l2.get_con().await?;
+
+		//get the remaining ttl with the value, so the local copy never outlives the key in redis
+		let (value, pttl): (Option<T>, i64) = redis::pipe()
+			.get(key)
+			.pttl(key)
+			.query_async(&mut con)
+			.await
+			.map_err(wrap_redis_error)?;
+
+		if let Some(v) = &value {
+			//-1 for keys without ttl
+			let ttl = if pttl < 0 {
+				self.l1_ttl
+			} else {
+				(pttl as usize / 1000).min(self.l1_ttl)
+			};
+
+			//don't cache keys which expire within the next second
+			if ttl > 0 {
+				self.l1.add(key.to_string(), v.clone(), ttl).await?;
+			}
+		}
+
+		Ok(value)
+	}
+
+	async fn add(&self, key: String, value: T, ttl: usize) -> AppRes<()>
+	{
+		self.l2.add(key.clone(), value.clone(), ttl).await?;
+
+		self.publish(vec![key.clone()]).await?;
+
+		self.l1.add(key, value, ttl.min(self.l1_ttl)).await
+	}
+
+	async fn get_and_touch(&self, key: &str, ttl: usize) -> AppRes<Option<T>>
+	{
+		//always go to l2 to set the ttl there
+		let value = self.l2.get_and_touch(key, ttl).await?;
+
+		if let Some(v) = &value {
+			self.l1
+				.add(key.to_string(), v.clone(), ttl.min(self.l1_ttl))
+				.await?;
+		}
+
+		Ok(value)
+	}
+
+	async fn ttl(&self, key: &str) -> AppRes<Option<Duration>>
+	{
+		self.l2.ttl(key).await
+	}
+
+	async fn expire(&self, key: &str, ttl: usize) -> AppRes<bool>
+	{
+		let exists = self.l2.expire(key, ttl).await?;
+
+		//the local copies might live longer than the new ttl
+		self.l1.delete(key).await?;
+		self.publish(vec![key.to_string()]).await?;
+
+		Ok(exists)
+	}
+
+	async fn delete(&self, key: &str) -> AppRes<()>
+	{
+		//l1 last, otherwise a get in between reads the old value from l2 into l1 again
+		self.l2.delete(key).await?;
+		self.publish(vec![key.to_string()]).await?;
+
+		self.l1.delete(key).await
+	}
+
+	async fn delete_multiple(&self, keys: &[&str]) -> AppRes<()>
+	{
+		self.l2.delete_multiple(keys).await?;
+		self.publish(keys.iter().map(|k| k.to_string()).collect())
+			.await?;
+
+		self.l1.delete_multiple(keys).await
+	}
+
+	async fn incr(&self, key: &str, by: i64, ttl: usize) -> AppRes<i64>
+	{
+		//counters must be shared between all nodes
+		self.l2.incr(key, by, ttl).await
+	}
+}
+
+#[async_trait]
+impl<T: 'static + Clone + Send + Sync> Lock for TieredCache<T>
+{
+	async fn acquire(&self, key: &str, token: &str, ttl: Duration) -> AppRes<bool>
+	{
+		self.l2.acquire(key, token, ttl).await
+	}
+
+	async fn renew(&self, key: &str, token: &str, ttl: Duration) -> AppRes<bool>
+	{
+		self.l2.renew(key, token, ttl).await
+	}
+
+	async fn release(&self, key: &str, token: &str) -> AppRes<bool>
+	{
+		self.l2.release(key, token).await
+	}
+}
@@ -0,0 +1,229 @@
+use std::future::Future;
+use std::io;
+use std::sync::OnceLock;
+
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZlibEncoder};
+use futures::StreamExt;
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, ETAG, VARY};
+use hyper::{Body, Method, StatusCode};
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use tokio_util::io::{ReaderStream, StreamReader};
+
+const DEFAULT_MIN_SIZE: u64 = 1024;
+
+static MIN_SIZE: OnceLock<u64> = OnceLock::new();
+
+/**
+Sets the min body size in bytes to compress. Smaller bodies are sent as they are. Default is 1024.
+ */
+pub fn set_compression_min_size(size: u64)
+{
+	let _ = MIN_SIZE.set(size);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding
+{
+	Br,
+	Gzip,
+	Deflate,
+}
+
+impl Encoding
+{
+	pub fn as_str(&self) -> &'static str
+	{
+		match self {
+			Encoding::Br => "br",
+			Encoding::Gzip => "gzip",
+			Encoding::Deflate => "deflate",
+		}
+	}
+}
+
+/**
+The best encoding of the accept encoding header. Br is used before gzip and deflate with the same quality.
+ */
+pub fn parse_accept_encoding(header: &str) -> Option<Encoding>
+{
+	let mut best: Option<(Encoding, f32)> = None;
+
+	for part in header.split(',') {
+		let mut split = part.trim().split(';');
+
+		let encoding = match split.next().map(|e| e.trim().to_lowercase()).as_deref() {
+			Some("br") => Encoding::Br,
+			Some("gzip") | Some("x-gzip") => Encoding::Gzip,
+			Some("deflate") => Encoding::Deflate,
+			_ => continue,
+		};
+
+		let q = split
+			.find_map(|p| p.trim().strip_prefix("q="))
+			.and_then(|q| q.parse().ok())
+			.unwrap_or(1.0);
+
+		//q=0 means not acceptable
+		if q <= 0.0 {
+			continue;
+		}
+
+		let better = match best {
+			Some((e, best_q)) => q > best_q || (q == best_q && rank(encoding) < rank(e)),
+			None => true,
+		};
+
+		if better {
+			best = Some((encoding, q));
+		}
+	}
+
+	best.map(|(e, _)| e)
+}
+
+fn rank(encoding: Encoding) -> u8
+{
+	match encoding {
+		Encoding::Br => 0,
+		Encoding::Gzip => 1,
+		Encoding::Deflate => 2,
+	}
+}
+
+fn is_compressible(content_type: &str) -> bool
+{
+	let content_type = content_type.to_lowercase();
+
+	//already compressed formats, event streams must not wait for the encoder buffer
+	if content_type.starts_with("image/") && !content_type.starts_with("image/svg") {
+		return false;
+	}
+
+	!(content_type.starts_with("video/") ||
+		content_type.starts_with("audio/") ||
+		content_type.starts_with("font/woff") ||
+		content_type.starts_with("text/event-stream") ||
+		content_type.starts_with("application/zip") ||
+		content_type.starts_with("application/gzip") ||
+		content_type.starts_with("application/x-gzip") ||
+		content_type.starts_with("application/octet-stream"))
+}
+
+fn compress_response(mut res: Response, encoding: Option<Encoding>) -> Response
+{
+	//partial content must stay the bytes of the range
+	if res.status() == StatusCode::NO_CONTENT ||
+		res.status() == StatusCode::NOT_MODIFIED ||
+		res.status() == StatusCode::PARTIAL_CONTENT ||
+		res.headers().contains_key(CONTENT_ENCODING)
+	{
+		return res;
+	}
+
+	let compressible = res
+		.headers()
+		.get(CONTENT_TYPE)
+		.and_then(|h| h.to_str().ok())
+		.is_some_and(is_compressible);
+
+	if !compressible {
+		return res;
+	}
+
+	res.headers_mut()
+		.append(VARY, HeaderValue::from_static("Accept-Encoding"));
+
+	let encoding = match encoding {
+		Some(e) => e,
+		None => return res,
+	};
+
+	//the size of the body if it is known, streams are always compressed
+	let size = res
+		.headers()
+		.get(CONTENT_LENGTH)
+		.and_then(|h| h.to_str().ok())
+		.and_then(|h| h.parse::<u64>().ok())
+		.or_else(|| res.body().size_hint().exact());
+
+	if size.is_some_and(|s| s < *MIN_SIZE.get().unwrap_or(&DEFAULT_MIN_SIZE)) {
+		return res;
+	}
+
+	let body = std::mem::take(res.body_mut());
+	let reader = StreamReader::new(body.map(|chunk| chunk.map_err(io::Error::other)));
+
+	*res.body_mut() = match encoding {
+		Encoding::Br => Body::wrap_stream(ReaderStream::new(BrotliEncoder::new(reader))),
+		Encoding::Gzip => Body::wrap_stream(ReaderStream::new(GzipEncoder::new(reader))),
+		Encoding::Deflate => Body::wrap_stream(ReaderStream::new(ZlibEncoder::new(reader))),
+	};
+
+	let headers = res.headers_mut();
+	headers.remove(CONTENT_LENGTH);
+	headers.insert(CONTENT_ENCODING, HeaderValue::from_static(encoding.as_str()));
+
+	//the compressed bytes are not the same as the uncompressed, so a strong etag must become weak
+	let weak = headers
+		.get(ETAG)
+		.and_then(|h| h.to_str().ok())
+		.filter(|e| !e.starts_with("W/"))
+		.and_then(|e| HeaderValue::from_str(&format!("W/{}", e)).ok());
+
+	if let Some(weak) = weak {
+		headers.insert(ETAG, weak);
+	}
+
+	res
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware to compress the response body
+
+Uses br, gzip or deflate by the accept encoding header. The body is compressed while it is streamed.
+
+Skipped are bodies smaller than the min size, already compressed content types like images or videos,
+event streams and responses with a content encoding, e.g. the precompressed files of the static server.
+
+````ignore
+router.get("/api/items", r(list).add(compression_transform));
+````
+ */
+pub struct Compression<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for Compression<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let encoding = if req.method() == Method::HEAD {
+			None
+		} else {
+			req.headers()
+				.get(hyper::header::ACCEPT_ENCODING)
+				.and_then(|h| h.to_str().ok())
+				.and_then(parse_accept_encoding)
+		};
+
+		let res = self.inner.call(req);
+
+		async move { compress_response(res.await, encoding) }
+	}
+}
+
+pub fn compression_transform<S>(inner: S) -> Compression<S>
+{
+	Compression {
+		inner,
+	}
+}
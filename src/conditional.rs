@@ -0,0 +1,168 @@
+use std::future::Future;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hyper::Method;
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use sha2::{Digest, Sha256};
+
+use crate::db::custom_types::date_str::DateTimeStr;
+
+tokio::task_local! {
+	static CONDITIONS: Conditions;
+}
+
+#[derive(Default, Clone)]
+struct Conditions
+{
+	if_none_match: Option<String>,
+	if_modified_since: Option<SystemTime>,
+}
+
+/**
+A strong etag from the hash of the body
+ */
+pub fn etag_from_body(body: &[u8]) -> String
+{
+	let hash = Sha256::digest(body);
+
+	//the first 16 bytes are enough for an etag
+	let hex: String = hash[..16].iter().map(|b| format!("{:02x}", b)).collect();
+
+	format!("\"{}\"", hex)
+}
+
+/**
+A strong etag from a version, e.g. a version column or an updated counter
+ */
+pub fn etag_from_version(version: impl std::fmt::Display) -> String
+{
+	format!("\"{}\"", version)
+}
+
+/**
+The time of a db timestamp column. The date time is read as utc.
+ */
+pub fn date_time_to_system_time(date_time: &DateTimeStr) -> SystemTime
+{
+	//days from the civil date, see http://howardhinnant.github.io/date_algorithms.html
+	let year = date_time.year as i64 - if date_time.month <= 2 { 1 } else { 0 };
+	let era = year.div_euclid(400);
+	let year_of_era = year - era * 400;
+	let month = date_time.month as i64;
+	let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + date_time.day as i64 - 1;
+	let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+	let days = era * 146_097 + day_of_era - 719_468;
+
+	let secs = days * 86_400 + date_time.hour as i64 * 3600 + date_time.minute as i64 * 60 + date_time.second as i64;
+
+	UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
+}
+
+pub fn http_date(time: SystemTime) -> String
+{
+	httpdate::fmt_http_date(time)
+}
+
+/**
+True if the etag matches the If-None-Match header of the request. The conditional get middleware is needed for the route.
+ */
+pub fn etag_matches(etag: &str) -> bool
+{
+	CONDITIONS
+		.try_with(|c| {
+			c.if_none_match.as_deref().is_some_and(|header| {
+				//weak comparison like in rfc 7232
+				header
+					.split(',')
+					.map(|t| t.trim())
+					.any(|t| t == "*" || t.trim_start_matches("W/") == etag.trim_start_matches("W/"))
+			})
+		})
+		.unwrap_or(false)
+}
+
+/**
+True if the resource was not modified since the If-Modified-Since header.
+The header is ignored when an If-None-Match is sent.
+ */
+pub fn not_modified_since(last_modified: SystemTime) -> bool
+{
+	CONDITIONS
+		.try_with(|c| {
+			if c.if_none_match.is_some() {
+				return false;
+			}
+
+			//the header has only seconds
+			let last_modified = last_modified
+				.duration_since(UNIX_EPOCH)
+				.map(|d| d.as_secs())
+				.unwrap_or(0);
+
+			c.if_modified_since
+				.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+				.is_some_and(|since| last_modified <= since.as_secs())
+		})
+		.unwrap_or(false)
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware for conditional get requests
+
+Reads the If-None-Match and If-Modified-Since headers for GET and HEAD requests,
+so JsonRes with an etag or last modified can respond with 304 Not Modified.
+
+````ignore
+async fn list(req: Request) -> JBRes<Vec<Item>>
+{
+	let items = ...;
+
+	Ok(JsonRes(items).etag())
+}
+
+router.get("/api/items", r(list).add(conditional_get_transform));
+````
+ */
+pub struct ConditionalGet<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for ConditionalGet<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let conditions = if req.method() == Method::GET || req.method() == Method::HEAD {
+			let headers = req.headers();
+
+			Conditions {
+				if_none_match: headers
+					.get(hyper::header::IF_NONE_MATCH)
+					.and_then(|h| h.to_str().ok())
+					.map(|h| h.to_string()),
+				if_modified_since: headers
+					.get(hyper::header::IF_MODIFIED_SINCE)
+					.and_then(|h| h.to_str().ok())
+					.and_then(|h| httpdate::parse_http_date(h).ok()),
+			}
+		} else {
+			Conditions::default()
+		};
+
+		CONDITIONS.scope(conditions, self.inner.call(req))
+	}
+}
+
+pub fn conditional_get_transform<S>(inner: S) -> ConditionalGet<S>
+{
+	ConditionalGet {
+		inner,
+	}
+}
@@ -0,0 +1,247 @@
+use std::fmt::{Display, Formatter};
+
+use crate::error::{server_err_owned, CoreErrorCodes, ServerCoreError};
+use crate::res::AppRes;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite
+{
+	Strict,
+	Lax,
+	None,
+}
+
+impl Display for SameSite
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		match self {
+			SameSite::Strict => write!(f, "Strict"),
+			SameSite::Lax => write!(f, "Lax"),
+			SameSite::None => write!(f, "None"),
+		}
+	}
+}
+
+/**
+# A cookie for the Set-Cookie header
+
+````ignore
+let cookie = Cookie::new("session", token)
+	.http_only()
+	.secure()
+	.same_site(SameSite::Strict)
+	.max_age(3600);
+
+echo(user).map(|res| res.cookie(cookie))
+````
+
+The name must be a token (rfc 6265), path and domain must not contain `;` or control chars.
+JsonResBuilder checks this with `check` and responds with a 500 for an invalid cookie.
+Bytes of the value which are not allowed in a cookie (e.g. `;`, `,`, whitespace and `%`) are percent-encoded.
+ */
+#[derive(Debug, Clone)]
+pub struct Cookie
+{
+	name: String,
+	value: String,
+	http_only: bool,
+	secure: bool,
+	same_site: Option<SameSite>,
+	max_age: Option<i64>,
+	path: Option<String>,
+	domain: Option<String>,
+}
+
+impl Cookie
+{
+	pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self
+	{
+		Self {
+			name: name.into(),
+			value: value.into(),
+			http_only: false,
+			secure: false,
+			same_site: None,
+			max_age: None,
+			path: Some("/".to_string()),
+			domain: None,
+		}
+	}
+
+	/**
+	A cookie to delete the cookie with the name in the client
+	 */
+	pub fn removal(name: impl Into<String>) -> Self
+	{
+		Self::new(name, "").max_age(0)
+	}
+
+	pub fn http_only(mut self) -> Self
+	{
+		self.http_only = true;
+		self
+	}
+
+	pub fn secure(mut self) -> Self
+	{
+		self.secure = true;
+		self
+	}
+
+	pub fn same_site(mut self, same_site: SameSite) -> Self
+	{
+		//browsers reject SameSite=None without secure
+		if same_site == SameSite::None {
+			self.secure = true;
+		}
+
+		self.same_site = Some(same_site);
+		self
+	}
+
+	/**
+	Max age in seconds
+	 */
+	pub fn max_age(mut self, seconds: i64) -> Self
+	{
+		self.max_age = Some(seconds);
+		self
+	}
+
+	pub fn path(mut self, path: impl Into<String>) -> Self
+	{
+		self.path = Some(path.into());
+		self
+	}
+
+	pub fn domain(mut self, domain: impl Into<String>) -> Self
+	{
+		self.domain = Some(domain.into());
+		self
+	}
+
+	pub fn name(&self) -> &str
+	{
+		&self.name
+	}
+
+	pub fn value(&self) -> &str
+	{
+		&self.value
+	}
+
+	/**
+	Checks that the name, the path and the domain can't break the Set-Cookie header
+	 */
+	pub fn check(&self) -> AppRes<()>
+	{
+		if !is_token(&self.name) {
+			return Err(cookie_err(format!(
+				"Cookie name is not a valid token: {:?}",
+				self.name
+			)));
+		}
+
+		if let Some(path) = &self.path {
+			if !is_attribute_value(path) {
+				return Err(cookie_err(format!("Cookie path is not valid: {:?}", path)));
+			}
+		}
+
+		if let Some(domain) = &self.domain {
+			if !is_domain(domain) {
+				return Err(cookie_err(format!("Cookie domain is not valid: {:?}", domain)));
+			}
+		}
+
+		Ok(())
+	}
+}
+
+fn cookie_err(debug: String) -> ServerCoreError
+{
+	server_err_owned(
+		500,
+		CoreErrorCodes::ResponseHeader,
+		"Can't create the response".to_string(),
+		Some(debug),
+	)
+}
+
+fn is_token(name: &str) -> bool
+{
+	const SEPARATORS: &[u8] = b"()<>@,;:\\\"/[]?={} \t";
+
+	!name.is_empty() &&
+		name.bytes()
+			.all(|b| b.is_ascii() && !b.is_ascii_control() && !SEPARATORS.contains(&b))
+}
+
+//no ; to start a new attribute and no control chars like a new line
+fn is_attribute_value(value: &str) -> bool
+{
+	value.bytes().all(|b| !b.is_ascii_control() && b != b';')
+}
+
+fn is_domain(domain: &str) -> bool
+{
+	!domain.is_empty() &&
+		domain
+			.bytes()
+			.all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.')
+}
+
+//cookie-octet of rfc 6265 without the %, so the value can be decoded again
+fn is_cookie_octet(b: u8) -> bool
+{
+	matches!(b, 0x21 | 0x23 | 0x24 | 0x26..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+fn encode_value(f: &mut Formatter<'_>, value: &str) -> std::fmt::Result
+{
+	for b in value.bytes() {
+		if is_cookie_octet(b) {
+			write!(f, "{}", b as char)?;
+		} else {
+			write!(f, "%{:02X}", b)?;
+		}
+	}
+
+	Ok(())
+}
+
+impl Display for Cookie
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		write!(f, "{}=", self.name)?;
+		encode_value(f, &self.value)?;
+
+		if let Some(max_age) = self.max_age {
+			write!(f, "; Max-Age={}", max_age)?;
+		}
+
+		if let Some(domain) = &self.domain {
+			write!(f, "; Domain={}", domain)?;
+		}
+
+		if let Some(path) = &self.path {
+			write!(f, "; Path={}", path)?;
+		}
+
+		if self.secure {
+			write!(f, "; Secure")?;
+		}
+
+		if self.http_only {
+			write!(f, "; HttpOnly")?;
+		}
+
+		if let Some(same_site) = self.same_site {
+			write!(f, "; SameSite={}", same_site)?;
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,347 @@
+use std::future::Future;
+use std::sync::OnceLock;
+
+use futures::future::{ready, Either};
+use hyper::header::{
+	HeaderValue,
+	ACCESS_CONTROL_ALLOW_CREDENTIALS,
+	ACCESS_CONTROL_ALLOW_HEADERS,
+	ACCESS_CONTROL_ALLOW_METHODS,
+	ACCESS_CONTROL_ALLOW_ORIGIN,
+	ACCESS_CONTROL_EXPOSE_HEADERS,
+	ACCESS_CONTROL_MAX_AGE,
+	ACCESS_CONTROL_REQUEST_HEADERS,
+	ACCESS_CONTROL_REQUEST_METHOD,
+	ORIGIN,
+	VARY,
+};
+use hyper::{Method, StatusCode};
+use regex::Regex;
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+
+static CORS_CONFIG: OnceLock<CorsConfig> = OnceLock::new();
+
+/**
+Sets the cors config for the cors middleware and the cors handler. Without a config every origin is allowed without credentials.
+
+Panics if every origin (`*`) is allowed with credentials, this would allow every site to use the cookies of the user.
+ */
+pub fn set_cors_config(config: CorsConfig)
+{
+	assert!(
+		!(config.allows_any_origin() && config.credentials),
+		"Cors config error: credentials are not allowed with the `*` origin, set the allowed origins instead."
+	);
+
+	let _ = CORS_CONFIG.set(config);
+}
+
+pub fn cors_config() -> &'static CorsConfig
+{
+	CORS_CONFIG.get_or_init(CorsConfig::default)
+}
+
+/**
+# Cors config
+
+Origins are allowed by the exact origin (e.g. `https://example.com`), by a regex or by `*` for every origin.
+
+With credentials the allowed origin is sent back. The `*` origin can't be used with credentials,
+`set_cors_config` panics for this config.
+
+````ignore
+set_cors_config(
+	CorsConfig::new()
+		.allow_origin("https://example.com")
+		.allow_origin_regex(Regex::new(r"^https://[a-z0-9-]+\.example\.com$").unwrap())
+		.allow_credentials(true)
+		.expose_header("X-Request-Id"),
+);
+````
+ */
+#[derive(Debug, Clone)]
+pub struct CorsConfig
+{
+	pub origins: Vec<String>,
+	pub origin_regex: Vec<Regex>,
+	pub methods: Vec<String>,
+	pub headers: Vec<String>,
+	pub expose_headers: Vec<String>,
+	pub max_age: Option<u64>,
+	pub credentials: bool,
+}
+
+impl Default for CorsConfig
+{
+	fn default() -> Self
+	{
+		Self {
+			origins: vec!["*".to_string()],
+			origin_regex: Vec::new(),
+			methods: ["GET", "POST", "PUT", "DELETE", "OPTIONS", "PATCH"]
+				.iter()
+				.map(|m| m.to_string())
+				.collect(),
+			headers: ["Content-Type", "Accept", "Origin", "Authorization", "X-Request-Id"]
+				.iter()
+				.map(|h| h.to_string())
+				.collect(),
+			expose_headers: Vec::new(),
+			max_age: Some(86400),
+			credentials: false,
+		}
+	}
+}
+
+impl CorsConfig
+{
+	/**
+	The default methods, headers and max age but without any allowed origin
+	 */
+	pub fn new() -> Self
+	{
+		Self {
+			origins: Vec::new(),
+			..Default::default()
+		}
+	}
+
+	pub fn allow_origin(mut self, origin: impl Into<String>) -> Self
+	{
+		self.origins.push(origin.into());
+		self
+	}
+
+	pub fn allow_origin_regex(mut self, regex: Regex) -> Self
+	{
+		self.origin_regex.push(regex);
+		self
+	}
+
+	/**
+	Replaces the default methods
+	 */
+	pub fn allow_methods(mut self, methods: &[&str]) -> Self
+	{
+		self.methods = methods.iter().map(|m| m.to_string()).collect();
+		self
+	}
+
+	/**
+	Replaces the default request headers
+	 */
+	pub fn allow_headers(mut self, headers: &[&str]) -> Self
+	{
+		self.headers = headers.iter().map(|h| h.to_string()).collect();
+		self
+	}
+
+	/**
+	A response header that the browser js can read
+	 */
+	pub fn expose_header(mut self, header: impl Into<String>) -> Self
+	{
+		self.expose_headers.push(header.into());
+		self
+	}
+
+	pub fn max_age(mut self, secs: Option<u64>) -> Self
+	{
+		self.max_age = secs;
+		self
+	}
+
+	pub fn allow_credentials(mut self, credentials: bool) -> Self
+	{
+		self.credentials = credentials;
+		self
+	}
+
+	pub(crate) fn allows_any_origin(&self) -> bool
+	{
+		self.origins.iter().any(|o| o == "*")
+	}
+
+	//never reflect the origin for the wildcard, even with credentials
+	fn sends_wildcard(&self) -> bool
+	{
+		self.allows_any_origin()
+	}
+
+	//browsers reject the wildcard with credentials, so no credentials for every origin
+	fn sends_credentials(&self) -> bool
+	{
+		self.credentials && !self.allows_any_origin()
+	}
+
+	pub fn is_origin_allowed(&self, origin: &str) -> bool
+	{
+		self.allows_any_origin() ||
+			self.origins.iter().any(|o| o.eq_ignore_ascii_case(origin)) ||
+			self.origin_regex.iter().any(|r| r.is_match(origin))
+	}
+
+	/**
+	The value for the allow origin header or None if the origin is not allowed
+	 */
+	fn allowed_origin(&self, origin: Option<&str>) -> Option<String>
+	{
+		match origin {
+			Some(origin) if self.is_origin_allowed(origin) => {
+				if self.sends_wildcard() {
+					Some("*".to_string())
+				} else {
+					Some(origin.to_string())
+				}
+			},
+			//requests without origin are no cors requests, the wildcard doesn't hurt here
+			None if self.sends_wildcard() => Some("*".to_string()),
+			_ => None,
+		}
+	}
+
+	/**
+	Sets the cors headers of a normal (no preflight) response
+	 */
+	pub fn apply(&self, origin: Option<&str>, res: &mut Response)
+	{
+		let headers = res.headers_mut();
+
+		//the response is different for each origin, so caches must know it
+		if !self.sends_wildcard() {
+			headers.append(VARY, HeaderValue::from_static("Origin"));
+		}
+
+		let allowed = match self
+			.allowed_origin(origin)
+			.and_then(|o| HeaderValue::from_str(&o).ok())
+		{
+			Some(o) => o,
+			None => {
+				headers.remove(ACCESS_CONTROL_ALLOW_ORIGIN);
+				return;
+			},
+		};
+
+		headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+
+		if self.sends_credentials() {
+			headers.insert(ACCESS_CONTROL_ALLOW_CREDENTIALS, HeaderValue::from_static("true"));
+		}
+
+		if !self.expose_headers.is_empty() {
+			if let Ok(v) = HeaderValue::from_str(&self.expose_headers.join(", ")) {
+				headers.insert(ACCESS_CONTROL_EXPOSE_HEADERS, v);
+			}
+		}
+	}
+
+	/**
+	The response for an options preflight request. Not allowed origins get a response without cors headers.
+	 */
+	pub fn preflight_response(&self, req: &Request) -> Response
+	{
+		let origin = req.headers().get(ORIGIN).and_then(|h| h.to_str().ok());
+
+		let mut builder = hyper::Response::builder()
+			.status(StatusCode::NO_CONTENT)
+			.header(
+				VARY,
+				"Origin, Access-Control-Request-Method, Access-Control-Request-Headers",
+			);
+
+		if let Some(allowed) = self.allowed_origin(origin) {
+			builder = builder
+				.header(ACCESS_CONTROL_ALLOW_ORIGIN, allowed)
+				.header(ACCESS_CONTROL_ALLOW_METHODS, self.methods.join(", "));
+
+			//the wildcard is not allowed with credentials, so send the requested headers back
+			let allow_headers = if self.headers.iter().any(|h| h == "*") && self.sends_credentials() {
+				req.headers()
+					.get(ACCESS_CONTROL_REQUEST_HEADERS)
+					.and_then(|h| h.to_str().ok())
+					.unwrap_or("")
+					.to_string()
+			} else {
+				self.headers.join(", ")
+			};
+
+			if !allow_headers.is_empty() {
+				builder = builder.header(ACCESS_CONTROL_ALLOW_HEADERS, allow_headers);
+			}
+
+			if let Some(max_age) = self.max_age {
+				builder = builder.header(ACCESS_CONTROL_MAX_AGE, max_age);
+			}
+
+			if self.sends_credentials() {
+				builder = builder.header(ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+			}
+		}
+
+		builder.body(hyper::Body::empty()).unwrap()
+	}
+}
+
+fn is_preflight(req: &Request) -> bool
+{
+	req.method() == Method::OPTIONS && req.headers().contains_key(ACCESS_CONTROL_REQUEST_METHOD)
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware for cors
+
+Responds to preflight requests without calling the handler and adds the cors headers to every other response.
+Uses the config of `set_cors_config`.
+
+````ignore
+router.get("/api/user", r(user_handler).add(cors_transform));
+router.options("/api/user", r(user_handler).add(cors_transform));
+````
+ */
+pub struct Cors<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for Cors<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let config = cors_config();
+
+		if is_preflight(&req) {
+			return Either::Left(ready(config.preflight_response(&req)));
+		}
+
+		let origin = req
+			.headers()
+			.get(ORIGIN)
+			.and_then(|h| h.to_str().ok())
+			.map(|h| h.to_string());
+
+		let next = self.inner.call(req);
+
+		Either::Right(async move {
+			let mut res = next.await;
+
+			config.apply(origin.as_deref(), &mut res);
+
+			res
+		})
+	}
+}
+
+pub fn cors_transform<S>(inner: S) -> Cors<S>
+{
+	Cors {
+		inner,
+	}
+}
@@ -3,6 +3,7 @@ use mysql_async::{OptsBuilder, Params, Pool, TxOpts};
 
 use crate::db::{db_bulk_insert_err, db_exec_err, db_query_err, db_tx_err};
 use crate::error::{CoreErrorCodes, ServerCoreError, ServerErrorConstructor};
+use crate::log;
 
 #[macro_export]
 macro_rules! take_or_err {
@@ -68,8 +69,7 @@ impl Mariadb
 {
 	pub fn new(user: &str, pw: &str, mysql_host: &str, db_name: &str, db_port: Option<u16>) -> Self
 	{
-		#[cfg(debug_assertions)]
-		println!("init mariadb");
+		log::debug("init mariadb");
 
 		let opts = if let Some(port) = db_port {
 			OptsBuilder::default()
@@ -93,8 +93,7 @@ impl Mariadb
 
 	pub fn new_with_conn_str(str: &str) -> Self
 	{
-		#[cfg(debug_assertions)]
-		println!("init mariadb");
+		log::debug("init mariadb");
 
 		Self {
 			pool: Pool::new(str),
@@ -103,8 +102,7 @@ impl Mariadb
 
 	pub fn new_with_pool(pool: Pool) -> Self
 	{
-		#[cfg(debug_assertions)]
-		println!("init mariadb");
+		log::debug("init mariadb");
 
 		Self {
 			pool,
@@ -6,6 +6,7 @@ use rusqlite::{params_from_iter, Connection, Row, ToSql};
 
 use crate::db::{db_bulk_insert_err, db_exec_err, db_query_err, db_tx_err};
 use crate::error::{CoreErrorCodes, ServerCoreError, ServerErrorConstructor};
+use crate::log;
 
 #[macro_export]
 macro_rules! take_or_err {
@@ -116,8 +117,7 @@ impl Sqlite
 
 		let pool = cfg.create_pool(Runtime::Tokio1).unwrap();
 
-		#[cfg(debug_assertions)]
-		println!("init sqlite");
+		log::debug("init sqlite");
 
 		Self {
 			pool,
@@ -126,8 +126,7 @@ impl Sqlite
 
 	pub fn new_with_config(cfg: Config) -> Self
 	{
-		#[cfg(debug_assertions)]
-		println!("init sqlite");
+		log::debug("init sqlite");
 
 		Self {
 			pool: cfg.create_pool(Runtime::Tokio1).unwrap(),
@@ -1,88 +1,132 @@
+use std::any::type_name;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
+use serde::Serialize;
+use serde_json::error::Category;
+
+use crate::input_helper::json_to_string;
+use crate::res::AppRes;
+
 pub trait ServerErrorCodes
 {
 	fn get_int_code(&self) -> u32;
 }
 
-#[derive(Debug)]
-pub enum CoreErrorCodes
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorCodeInfo
 {
-	IdWrongFormat,
-
-	JsonToString,
-	JsonParse,
-
-	InputTooBig,
+	pub code: u32,
+	pub name: &'static str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub status: Option<u16>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub msg: Option<&'static str>,
+}
 
-	UnexpectedTime,
+/**
+# List of every code of an error enum
 
-	DbQuery,
-	DbExecute,
-	DbBulkInsert,
-	DbTx,
-	NoDbConnection,
+Use the ServerErrorCodes derive from the derive_macro feature to implement this.
+ */
+pub trait ErrorCodeCatalog: ServerErrorCodes
+{
+	fn catalog() -> Vec<ErrorCodeInfo>;
+}
 
-	EmailMessage,
-	EmailSend,
+fn core_info(code: CoreErrorCodes, name: &'static str) -> ErrorCodeInfo
+{
+	ErrorCodeInfo {
+		code: code.get_int_code(),
+		name,
+		status: None,
+		msg: None,
+	}
+}
 
-	NoParameter,
-	NoUrlQuery,
+//the enum, the int codes and the catalog from one table, so no variant is missing in the catalog
+macro_rules! core_error_codes {
+	($($variant:ident => $code:expr,)*) => {
+		#[derive(Debug)]
+		pub enum CoreErrorCodes
+		{
+			$($variant,)*
+		}
 
-	PageNotFound,
+		impl ServerErrorCodes for CoreErrorCodes
+		{
+			fn get_int_code(&self) -> u32
+			{
+				match self {
+					$(Self::$variant => $code,)*
+				}
+			}
+		}
 
-	FileLocalOpen,
-	FileRemove,
-	FileSave,
-	FileTooLarge,
-	FileDownload,
+		impl ErrorCodeCatalog for CoreErrorCodes
+		{
+			fn catalog() -> Vec<ErrorCodeInfo>
+			{
+				vec![$(core_info(Self::$variant, stringify!($variant)),)*]
+			}
+		}
+	};
+}
 
-	RedisError,
+core_error_codes! {
+	IdWrongFormat => 1,
 
-	DateStrParse,
-}
+	JsonToString => 10,
+	JsonParse => 11,
+	InputTooBig => 12,
+	UnexpectedTime => 13,
+	Io => 14,
+	InputValidation => 15,
+	InputRead => 16,
+	MultipartParse => 17,
+	UnsupportedEncoding => 18,
+	UnsupportedMediaType => 19,
 
-impl ServerErrorCodes for CoreErrorCodes
-{
-	fn get_int_code(&self) -> u32
-	{
-		match self {
-			CoreErrorCodes::IdWrongFormat => 1,
+	NoDbConnection => 20,
+	DbQuery => 21,
+	DbExecute => 22,
+	DbBulkInsert => 23,
+	DbTx => 24,
 
-			CoreErrorCodes::JsonToString => 10,
-			CoreErrorCodes::JsonParse => 11,
-			CoreErrorCodes::InputTooBig => 12,
-			CoreErrorCodes::UnexpectedTime => 13,
+	NoParameter => 40,
+	NoUrlQuery => 41,
+	UrlQueryParse => 42,
+	ParamParse => 43,
+	ParamOutOfRange => 44,
 
-			CoreErrorCodes::NoDbConnection => 20,
-			CoreErrorCodes::DbQuery => 21,
-			CoreErrorCodes::DbExecute => 22,
-			CoreErrorCodes::DbBulkInsert => 23,
-			CoreErrorCodes::DbTx => 24,
+	EmailSend => 50,
+	EmailMessage => 51,
 
-			CoreErrorCodes::NoParameter => 40,
-			CoreErrorCodes::NoUrlQuery => 41,
+	RedisError => 52,
+	CacheUnsupported => 53,
 
-			CoreErrorCodes::EmailSend => 50,
-			CoreErrorCodes::EmailMessage => 51,
+	DateStrParse => 60,
 
-			CoreErrorCodes::RedisError => 52,
+	RateLimitExceeded => 70,
 
-			CoreErrorCodes::DateStrParse => 60,
+	ResponseHeader => 80,
 
-			Self::PageNotFound => 404,
+	PageNotFound => 404,
 
-			CoreErrorCodes::FileLocalOpen => 500,
-			CoreErrorCodes::FileRemove => 501,
-			CoreErrorCodes::FileSave => 502,
-			CoreErrorCodes::FileDownload => 503,
-			CoreErrorCodes::FileTooLarge => 504,
-		}
-	}
+	FileLocalOpen => 500,
+	FileRemove => 501,
+	FileSave => 502,
+	FileDownload => 503,
+	FileTooLarge => 504,
 }
 
+/**
+# The error of every AppRes
+
+Create it with `server_err`, `server_err_owned` or the ServerErrorConstructor, the struct can get new fields.
+ */
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct ServerCoreError
 {
 	pub http_status_code: u16,
@@ -90,6 +134,32 @@ pub struct ServerCoreError
 	pub msg: &'static str,
 	pub msg_owned: Option<String>, //msg will be ignored if this is set
 	pub debug_msg: Option<String>,
+	pub source: Option<Box<dyn Error + Send + Sync>>, //the error which caused this error
+	//boxed slices instead of vecs keep the error small, every AppRes carries it (clippy::result_large_err)
+	pub msg_params: Box<[(&'static str, String)]>, //params for the translated msg
+	pub errors: Box<[FieldError]>,                 //errors of single input fields, e.g. from the validation
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct FieldError
+{
+	//empty for errors of the whole input, e.g. a parse error of the body
+	#[serde(skip_serializing_if = "String::is_empty")]
+	pub field: String,
+	pub code: &'static str,
+	pub message: String,
+}
+
+impl FieldError
+{
+	pub fn new(field: &str, code: &'static str, message: String) -> Self
+	{
+		Self {
+			field: field.to_string(),
+			code,
+			message,
+		}
+	}
 }
 
 impl Display for ServerCoreError
@@ -103,7 +173,50 @@ impl Display for ServerCoreError
 	}
 }
 
-impl Error for ServerCoreError {}
+impl Error for ServerCoreError
+{
+	fn source(&self) -> Option<&(dyn Error + 'static)>
+	{
+		match &self.source {
+			Some(e) => Some(e.as_ref()),
+			None => None,
+		}
+	}
+}
+
+impl ServerCoreError
+{
+	pub fn with_source(mut self, source: impl Error + Send + Sync + 'static) -> Self
+	{
+		self.source = Some(Box::new(source));
+
+		self
+	}
+
+	/**
+	Adds a param for the translated message, e.g. `{max_chunk_size}`
+	 */
+	pub fn with_param(mut self, name: &'static str, value: impl ToString) -> Self
+	{
+		//boxed slices to keep the error small, the params are only set a few times
+		let mut params = std::mem::take(&mut self.msg_params).into_vec();
+		params.push((name, value.to_string()));
+
+		self.msg_params = params.into_boxed_slice();
+
+		self
+	}
+
+	/**
+	Adds the errors of single fields, they are rendered in the `errors` array of the response.
+	 */
+	pub fn with_errors(mut self, errors: Vec<FieldError>) -> Self
+	{
+		self.errors = errors.into_boxed_slice();
+
+		self
+	}
+}
 
 pub trait ServerErrorConstructor: Sized
 {
@@ -137,6 +250,9 @@ impl ServerErrorConstructor for ServerCoreError
 			msg,
 			msg_owned,
 			debug_msg,
+			source: None,
+			msg_params: Box::default(),
+			errors: Box::default(),
 		}
 	}
 }
@@ -150,3 +266,255 @@ pub fn server_err_owned(http_status_code: u16, error_code: impl ServerErrorCodes
 {
 	ServerCoreError::new_msg_owned(http_status_code, error_code, msg_owned, debug_msg)
 }
+
+/**
+Renders the error and every source of it, e.g. `db error: caused by: connection refused`
+ */
+pub fn error_chain(e: &(dyn Error + 'static)) -> String
+{
+	let mut out = e.to_string();
+	let mut source = e.source();
+
+	while let Some(s) = source {
+		out += ": caused by: ";
+		out += &s.to_string();
+
+		source = s.source();
+	}
+
+	out
+}
+
+fn from_source<E: Error + Send + Sync + 'static>(http_status_code: u16, error_code: CoreErrorCodes, msg: &'static str, e: E) -> ServerCoreError
+{
+	ServerCoreError::new_msg_and_debug(http_status_code, error_code, msg, Some(error_chain(&e))).with_source(e)
+}
+
+/**
+# Adds a context to an error
+
+The original error is kept as source and is rendered into the debug msg.
+
+````ignore
+let file = File::open(path).await.context(400, CoreErrorCodes::FileLocalOpen, "Can't open the file")?;
+````
+ */
+pub trait ResultContext<T>
+{
+	fn context(self, http_status_code: u16, error_code: impl ServerErrorCodes, msg: &'static str) -> Result<T, ServerCoreError>;
+}
+
+impl<T, E: Error + Send + Sync + 'static> ResultContext<T> for Result<T, E>
+{
+	fn context(self, http_status_code: u16, error_code: impl ServerErrorCodes, msg: &'static str) -> Result<T, ServerCoreError>
+	{
+		self.map_err(|e| ServerCoreError::new_msg_and_debug(http_status_code, error_code, msg, Some(error_chain(&e))).with_source(e))
+	}
+}
+
+impl From<std::io::Error> for ServerCoreError
+{
+	fn from(e: std::io::Error) -> Self
+	{
+		from_source(500, CoreErrorCodes::Io, "Io error", e)
+	}
+}
+
+impl From<serde_json::Error> for ServerCoreError
+{
+	fn from(e: serde_json::Error) -> Self
+	{
+		//serialize errors have no position (line 0), they are bugs of the server and not wrong input
+		if e.classify() != Category::Io && e.line() > 0 {
+			from_source(422, CoreErrorCodes::JsonParse, "Wrong input", e)
+		} else {
+			from_source(500, CoreErrorCodes::JsonToString, "Can't create the output", e)
+		}
+	}
+}
+
+impl From<redis::RedisError> for ServerCoreError
+{
+	fn from(e: redis::RedisError) -> Self
+	{
+		from_source(400, CoreErrorCodes::RedisError, "Error with redis cache", e)
+	}
+}
+
+#[cfg(feature = "mysql")]
+impl From<mysql_async::Error> for ServerCoreError
+{
+	fn from(e: mysql_async::Error) -> Self
+	{
+		from_source(422, CoreErrorCodes::DbQuery, "db error", e)
+	}
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for ServerCoreError
+{
+	fn from(e: rusqlite::Error) -> Self
+	{
+		from_source(422, CoreErrorCodes::DbQuery, "db error", e)
+	}
+}
+
+//__________________________________________________________________________________________________
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RegisteredErrorCode
+{
+	pub source: &'static str,
+	#[serde(flatten)]
+	pub info: ErrorCodeInfo,
+}
+
+#[derive(Debug)]
+pub struct ErrorCodeCollision
+{
+	pub code: u32,
+	pub first: RegisteredErrorCode,
+	pub second: RegisteredErrorCode,
+}
+
+impl Display for ErrorCodeCollision
+{
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		write!(
+			f,
+			"Error code {} is used by {}::{} and {}::{}",
+			self.code, self.first.source, self.first.info.name, self.second.source, self.second.info.name
+		)
+	}
+}
+
+/**
+# Registry of every error code of the app
+
+The core codes are registered by default. Check the registry at startup or in a test, so two enums can't use the same code.
+
+````ignore
+let registry = ErrorRegistry::new().register::<AppErrorCodes>();
+
+registry.check().unwrap();
+
+std::fs::write("errors.md", registry.to_markdown()).unwrap();
+````
+ */
+pub struct ErrorRegistry
+{
+	codes: Vec<RegisteredErrorCode>,
+}
+
+impl Default for ErrorRegistry
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+
+impl ErrorRegistry
+{
+	pub fn new() -> Self
+	{
+		Self {
+			codes: Vec::new(),
+		}
+		.register::<CoreErrorCodes>()
+	}
+
+	pub fn register<E: ErrorCodeCatalog>(mut self) -> Self
+	{
+		let source = type_name::<E>();
+
+		for info in E::catalog() {
+			self.codes.push(RegisteredErrorCode {
+				source,
+				info,
+			});
+		}
+
+		self
+	}
+
+	pub fn codes(&self) -> &[RegisteredErrorCode]
+	{
+		&self.codes
+	}
+
+	pub fn collisions(&self) -> Vec<ErrorCodeCollision>
+	{
+		let mut collisions = Vec::new();
+
+		for (i, first) in self.codes.iter().enumerate() {
+			for second in &self.codes[i + 1..] {
+				if first.info.code == second.info.code {
+					collisions.push(ErrorCodeCollision {
+						code: first.info.code,
+						first: first.clone(),
+						second: second.clone(),
+					});
+				}
+			}
+		}
+
+		collisions
+	}
+
+	/**
+	Returns an error with every duplicate code
+	 */
+	pub fn check(&self) -> Result<(), String>
+	{
+		let collisions = self.collisions();
+
+		if collisions.is_empty() {
+			return Ok(());
+		}
+
+		Err(collisions
+			.iter()
+			.map(|c| c.to_string())
+			.collect::<Vec<_>>()
+			.join("\n"))
+	}
+
+	/**
+	The catalog as json array sorted by the code
+	 */
+	pub fn to_json(&self) -> AppRes<String>
+	{
+		json_to_string(&self.sorted())
+	}
+
+	/**
+	The catalog as markdown table sorted by the code
+	 */
+	pub fn to_markdown(&self) -> String
+	{
+		let mut out = "| Code | Name | Http status | Message | Source |\n|---|---|---|---|---|\n".to_string();
+
+		for c in self.sorted() {
+			out += &format!(
+				"| {} | {} | {} | {} | {} |\n",
+				c.info.code,
+				c.info.name,
+				c.info.status.map(|s| s.to_string()).unwrap_or_default(),
+				c.info.msg.unwrap_or(""),
+				c.source
+			);
+		}
+
+		out
+	}
+
+	fn sorted(&self) -> Vec<&RegisteredErrorCode>
+	{
+		let mut codes: Vec<&RegisteredErrorCode> = self.codes.iter().collect();
+		codes.sort_by_key(|c| c.info.code);
+
+		codes
+	}
+}
@@ -1,13 +1,12 @@
 use async_trait::async_trait;
 use futures::StreamExt;
-use hyper::Body;
+use hyper::HeaderMap;
 use rustgram::{Request, Response};
 use tokio::fs::{remove_file, File};
 use tokio::io::AsyncWriteExt;
-use tokio_util::codec::{BytesCodec, FramedRead};
 
 use crate::error::{server_err, server_err_owned, CoreErrorCodes, ServerCoreError, ServerErrorConstructor};
-use crate::file::FileHandler;
+use crate::file::{file_response, FileHandler};
 use crate::res::AppRes;
 
 pub struct LocalStorage
@@ -43,27 +42,15 @@ impl FileHandler for LocalStorage
 {
 	async fn get_part(&self, part_id: &str, content_type: Option<&str>) -> AppRes<Response>
 	{
-		let path = self.path.to_string() + "/" + part_id;
-
-		let file = File::open(path.as_str()).await.map_err(|e| {
-			server_err_owned(
-				400,
-				CoreErrorCodes::FileLocalOpen,
-				format!("error in open file: {}", e),
-				None,
-			)
-		})?;
-
-		let stream = FramedRead::new(file, BytesCodec::new());
-		let body = Body::wrap_stream(stream);
+		self.get_part_with_range(part_id, content_type, &HeaderMap::new())
+			.await
+	}
 
-		let content_type = content_type.unwrap_or("application/octet-stream");
+	async fn get_part_with_range(&self, part_id: &str, content_type: Option<&str>, headers: &HeaderMap) -> AppRes<Response>
+	{
+		let path = self.path.to_string() + "/" + part_id;
 
-		hyper::Response::builder()
-			.header("Content-Type", content_type)
-			.header("Access-Control-Allow-Origin", "*")
-			.body(body)
-			.map_err(|_e| server_err(400, CoreErrorCodes::DbBulkInsert, "Can't download the file"))
+		file_response(&path, content_type.unwrap_or("application/octet-stream"), headers).await
 	}
 
 	async fn upload_part(&self, req: Request, part_id: &str, max_chunk_size: usize) -> AppRes<usize>
@@ -80,8 +67,16 @@ impl FileHandler for LocalStorage
 		while let Some(bytes) = body.next().await {
 			let bytes = match bytes {
 				Ok(b) => b,
-				Err(_e) => {
-					continue;
+				Err(e) => {
+					//don't keep a truncated file
+					self.remove_file(path.as_str()).await?;
+
+					return Err(server_err_owned(
+						400,
+						CoreErrorCodes::InputRead,
+						"Can't read the input".to_string(),
+						Some(e.to_string()),
+					));
 				},
 			};
 
@@ -98,7 +93,8 @@ impl FileHandler for LocalStorage
 						max_chunk_size
 					),
 					None,
-				));
+				)
+				.with_param("max_chunk_size", max_chunk_size));
 			}
 
 			file.write_all(&bytes).await.map_err(|e| {
@@ -113,6 +109,16 @@ impl FileHandler for LocalStorage
 			size += b_len;
 		}
 
+		//tokio writes in the background, so the file must be complete before it is used
+		file.flush().await.map_err(|e| {
+			server_err_owned(
+				400,
+				CoreErrorCodes::FileSave,
+				"Can't save the file".to_string(),
+				Some(format!("error in saving a file: {}, error: {}", part_id, e)),
+			)
+		})?;
+
 		Ok(size)
 	}
 
@@ -1,7 +1,10 @@
 mod local_storage;
+mod range;
 
 use async_trait::async_trait;
+use hyper::HeaderMap;
 pub use local_storage::LocalStorage;
+pub use range::{file_response, parse_range, RangeResult};
 use rustgram::{Request, Response};
 
 use crate::error::ServerCoreError;
@@ -13,6 +16,15 @@ pub trait FileHandler: Send + Sync
 {
 	async fn get_part(&self, part_id: &str, content_type: Option<&str>) -> Result<Response, ServerCoreError>;
 
+	/**
+	Like get_part but with the Range and If-Range headers of the request for partial content.
+	The default ignores the range and sends the whole part.
+	 */
+	async fn get_part_with_range(&self, part_id: &str, content_type: Option<&str>, _headers: &HeaderMap) -> Result<Response, ServerCoreError>
+	{
+		self.get_part(part_id, content_type).await
+	}
+
 	async fn upload_part(&self, req: Request, part_id: &str, max_chunk_size: usize) -> Result<usize, ServerCoreError>;
 
 	async fn delete_part(&self, part_id: &str) -> Result<(), ServerCoreError>;
@@ -0,0 +1,304 @@
+use std::collections::VecDeque;
+use std::io::SeekFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use futures::stream;
+use hyper::header::{HeaderMap, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, IF_RANGE, LAST_MODIFIED, RANGE};
+use hyper::{Body, StatusCode};
+use rustgram::Response;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::conditional::http_date;
+use crate::error::{server_err_owned, CoreErrorCodes, ServerCoreError};
+use crate::res::AppRes;
+
+//more ranges are not useful and only cost server resources
+const MAX_RANGES: usize = 32;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RangeResult
+{
+	/**
+	No or an invalid range header, the whole file is sent
+	 */
+	Full,
+	/**
+	The inclusive start and end of every range
+	 */
+	Partial(Vec<(u64, u64)>),
+	Unsatisfiable,
+}
+
+/**
+Parses a range header like `bytes=0-499, 1000-, -500` for a file with the size.
+
+Invalid headers are ignored like in rfc 9110. Ranges after the end of the file are not satisfiable.
+
+Overlapping and adjacent ranges are merged. If the ranges together are larger than the file, the whole file is sent.
+ */
+pub fn parse_range(header: &str, size: u64) -> RangeResult
+{
+	let specs = match header.trim().strip_prefix("bytes=") {
+		Some(s) => s,
+		None => return RangeResult::Full,
+	};
+
+	let mut ranges = Vec::new();
+
+	for spec in specs.split(',') {
+		let (start, end) = match spec.trim().split_once('-') {
+			Some(s) => s,
+			None => return RangeResult::Full,
+		};
+
+		let (start, end) = (start.trim(), end.trim());
+
+		let range = if start.is_empty() {
+			//the last bytes of the file
+			let len: u64 = match end.parse() {
+				Ok(l) => l,
+				Err(_) => return RangeResult::Full,
+			};
+
+			if len == 0 || size == 0 {
+				None
+			} else {
+				Some((size.saturating_sub(len), size - 1))
+			}
+		} else {
+			let start: u64 = match start.parse() {
+				Ok(s) => s,
+				Err(_) => return RangeResult::Full,
+			};
+
+			let end: u64 = if end.is_empty() {
+				u64::MAX
+			} else {
+				match end.parse() {
+					Ok(e) => e,
+					Err(_) => return RangeResult::Full,
+				}
+			};
+
+			if end < start {
+				return RangeResult::Full;
+			}
+
+			if start >= size {
+				None
+			} else {
+				Some((start, end.min(size - 1)))
+			}
+		};
+
+		if let Some(range) = range {
+			ranges.push(range);
+		}
+	}
+
+	if ranges.len() > MAX_RANGES {
+		return RangeResult::Full;
+	}
+
+	if ranges.is_empty() {
+		return RangeResult::Unsatisfiable;
+	}
+
+	//e.g. bytes=0-,0-,0- would send the file many times
+	let total: u64 = ranges.iter().map(|(start, end)| end - start + 1).sum();
+
+	if total > size {
+		return RangeResult::Full;
+	}
+
+	ranges.sort_unstable();
+
+	let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+
+	for (start, end) in ranges {
+		match merged.last_mut() {
+			Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+			_ => merged.push((start, end)),
+		}
+	}
+
+	RangeResult::Partial(merged)
+}
+
+/**
+True if the range should be used. The If-Range header must match the last modified time, etags are not supported.
+ */
+fn if_range_matches(headers: &HeaderMap, last_modified: Option<SystemTime>) -> bool
+{
+	let if_range = match headers.get(IF_RANGE).and_then(|h| h.to_str().ok()) {
+		Some(h) => h,
+		None => return true,
+	};
+
+	let secs = |t: SystemTime| t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).ok();
+
+	match (httpdate::parse_http_date(if_range), last_modified) {
+		(Ok(date), Some(modified)) => secs(date).is_some() && secs(date) == secs(modified),
+		_ => false,
+	}
+}
+
+fn read_err(e: std::io::Error) -> ServerCoreError
+{
+	server_err_owned(
+		400,
+		CoreErrorCodes::FileDownload,
+		"Can't download the file".to_string(),
+		Some(format!("error in reading file: {}", e)),
+	)
+}
+
+//read buffer for the multipart ranges
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+//the parts of a multipart response, the ranges are read from the same file
+enum Part
+{
+	Head(Bytes),
+	Range(u64, u64),
+	Tail(Bytes),
+}
+
+async fn next_part(file: &mut File, parts: &mut VecDeque<Part>) -> Option<std::io::Result<Bytes>>
+{
+	match parts.pop_front()? {
+		Part::Head(b) | Part::Tail(b) => Some(Ok(b)),
+		Part::Range(start, len) => {
+			let chunk = len.min(CHUNK_SIZE);
+
+			let read = async {
+				file.seek(SeekFrom::Start(start)).await?;
+
+				let mut buf = vec![0; chunk as usize];
+				file.read_exact(&mut buf).await?;
+
+				Ok(Bytes::from(buf))
+			};
+
+			//the rest of the range in the next call
+			if len > chunk {
+				parts.push_front(Part::Range(start + chunk, len - chunk));
+			}
+
+			Some(read.await)
+		},
+	}
+}
+
+/**
+The response for the file with the Range and If-Range headers of the request.
+
+Sends 206 for a single range, `multipart/byteranges` for more ranges, 416 if no range is satisfiable
+and the whole file with `Accept-Ranges` and `Content-Length` without a range.
+ */
+pub async fn file_response(path: &str, content_type: &str, headers: &HeaderMap) -> AppRes<Response>
+{
+	let file = File::open(path).await.map_err(|e| {
+		server_err_owned(
+			400,
+			CoreErrorCodes::FileLocalOpen,
+			format!("error in open file: {}", e),
+			None,
+		)
+	})?;
+
+	let metadata = file.metadata().await.map_err(read_err)?;
+	let size = metadata.len();
+	let last_modified = metadata.modified().ok();
+
+	let range = match headers.get(RANGE).and_then(|h| h.to_str().ok()) {
+		Some(range) if if_range_matches(headers, last_modified) => parse_range(range, size),
+		_ => RangeResult::Full,
+	};
+
+	let mut builder = hyper::Response::builder().header(ACCEPT_RANGES, "bytes");
+
+	if let Some(modified) = last_modified {
+		builder = builder.header(LAST_MODIFIED, http_date(modified));
+	}
+
+	let res = match range {
+		RangeResult::Full => {
+			builder
+				.header(CONTENT_TYPE, content_type)
+				.header(CONTENT_LENGTH, size)
+				.body(Body::wrap_stream(ReaderStream::new(file)))
+		},
+		RangeResult::Unsatisfiable => {
+			builder
+				.status(StatusCode::RANGE_NOT_SATISFIABLE)
+				.header(CONTENT_RANGE, format!("bytes */{}", size))
+				.body(Body::empty())
+		},
+		RangeResult::Partial(ranges) if ranges.len() == 1 => {
+			let (start, end) = ranges[0];
+			let len = end - start + 1;
+
+			let mut file = file;
+			file.seek(SeekFrom::Start(start)).await.map_err(read_err)?;
+
+			builder
+				.status(StatusCode::PARTIAL_CONTENT)
+				.header(CONTENT_TYPE, content_type)
+				.header(CONTENT_LENGTH, len)
+				.header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size))
+				.body(Body::wrap_stream(ReaderStream::new(file.take(len))))
+		},
+		RangeResult::Partial(ranges) => {
+			let boundary = Uuid::new_v4().simple().to_string();
+
+			let mut parts = VecDeque::with_capacity(ranges.len() * 2 + 1);
+			let mut len = 0;
+
+			for (start, end) in ranges {
+				let head = format!(
+					"\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+					boundary, content_type, start, end, size
+				);
+
+				len += head.len() as u64 + end - start + 1;
+
+				parts.push_back(Part::Head(Bytes::from(head)));
+				parts.push_back(Part::Range(start, end - start + 1));
+			}
+
+			let tail = format!("\r\n--{}--\r\n", boundary);
+			len += tail.len() as u64;
+
+			parts.push_back(Part::Tail(Bytes::from(tail)));
+
+			//the ranges are read from the opened file, so all parts are from the same file
+			let body = stream::unfold((file, parts), |(mut file, mut parts)| {
+				async move {
+					let chunk = next_part(&mut file, &mut parts).await?;
+
+					Some((chunk, (file, parts)))
+				}
+			});
+
+			builder
+				.status(StatusCode::PARTIAL_CONTENT)
+				.header(CONTENT_TYPE, format!("multipart/byteranges; boundary={}", boundary))
+				.header(CONTENT_LENGTH, len)
+				.body(Body::wrap_stream(body))
+		},
+	};
+
+	res.map_err(|_e| {
+		server_err_owned(
+			400,
+			CoreErrorCodes::FileDownload,
+			"Can't download the file".to_string(),
+			None,
+		)
+	})
+}
@@ -0,0 +1,199 @@
+use std::future::Future;
+
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{server_err, server_err_owned, CoreErrorCodes};
+use crate::input_helper::{bytes_to_json, input_err, json_to_string};
+use crate::res::AppRes;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+tokio::task_local! {
+	static RESPONSE_FORMAT: Format;
+}
+
+/**
+# The encoding of the body
+
+Json is the default. MessagePack and CBOR are more compact, e.g. for calls between services.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format
+{
+	Json,
+	MsgPack,
+	Cbor,
+}
+
+impl Format
+{
+	pub fn content_type(&self) -> &'static str
+	{
+		match self {
+			Format::Json => JSON_CONTENT_TYPE,
+			Format::MsgPack => MSGPACK_CONTENT_TYPE,
+			Format::Cbor => CBOR_CONTENT_TYPE,
+		}
+	}
+
+	/**
+	The format of a single media type, e.g. from the content type header
+	 */
+	pub fn from_media_type(media_type: &str) -> Option<Self>
+	{
+		//ignore the params like charset
+		let media_type = media_type.split(';').next()?.trim().to_lowercase();
+
+		match media_type.as_str() {
+			"application/json" => Some(Format::Json),
+			"application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Format::MsgPack),
+			"application/cbor" => Some(Format::Cbor),
+			_ => None,
+		}
+	}
+
+	/**
+	The supported format with the highest q value of the accept header, the first one for the same q.
+	Json if nothing is supported.
+	 */
+	pub fn from_accept(header: &str) -> Self
+	{
+		let mut best: Option<(Format, f32)> = None;
+
+		for part in header.split(',') {
+			let format = match Self::from_media_type(part) {
+				Some(f) => f,
+				None => continue,
+			};
+
+			let q = part
+				.split(';')
+				.skip(1)
+				.find_map(|p| p.trim().strip_prefix("q="))
+				.and_then(|q| q.parse().ok())
+				.unwrap_or(1.0);
+
+			//q=0 means not acceptable
+			if q <= 0.0 {
+				continue;
+			}
+
+			let better = match best {
+				Some((_, best_q)) => q > best_q,
+				None => true,
+			};
+
+			if better {
+				best = Some((format, q));
+			}
+		}
+
+		best.map(|(f, _)| f).unwrap_or(Format::Json)
+	}
+
+	pub fn serialize<T: ?Sized + Serialize>(&self, value: &T) -> AppRes<Vec<u8>>
+	{
+		match self {
+			Format::Json => Ok(json_to_string(value)?.into_bytes()),
+			Format::MsgPack => {
+				//with the field names, so the client gets the same output as with json
+				rmp_serde::to_vec_named(value).map_err(|e| server_err_owned(422, CoreErrorCodes::JsonToString, format!("msgpack err: {}", e), None))
+			},
+			Format::Cbor => {
+				let mut out = Vec::new();
+
+				ciborium::into_writer(value, &mut out)
+					.map_err(|e| server_err_owned(422, CoreErrorCodes::JsonToString, format!("cbor err: {}", e), None))?;
+
+				Ok(out)
+			},
+		}
+	}
+
+	pub fn deserialize<T: DeserializeOwned>(&self, v: &[u8]) -> AppRes<T>
+	{
+		match self {
+			Format::Json => bytes_to_json(v),
+			Format::MsgPack => rmp_serde::from_slice(v).map_err(|e| input_err("msgpack", e)),
+			Format::Cbor => ciborium::from_reader(v).map_err(|e| input_err("cbor", e)),
+		}
+	}
+}
+
+/**
+The format of the request body by the content type header. Json if the header is not set.
+ */
+pub fn get_body_format(req: &Request) -> AppRes<Format>
+{
+	let content_type = match req.headers().get(hyper::header::CONTENT_TYPE) {
+		Some(h) => h,
+		None => return Ok(Format::Json),
+	};
+
+	content_type
+		.to_str()
+		.ok()
+		.and_then(Format::from_media_type)
+		.ok_or_else(|| {
+			server_err(
+				415,
+				CoreErrorCodes::UnsupportedMediaType,
+				"Content type is not supported",
+			)
+		})
+}
+
+/**
+The negotiated format for the response. The content negotiation middleware is needed for the route.
+ */
+pub fn current_format() -> Format
+{
+	RESPONSE_FORMAT.try_with(|f| *f).unwrap_or(Format::Json)
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware to choose the response format by the accept header
+
+Used by `Res` and the error responses.
+
+````ignore
+router.get("/api/user", r(user_handler).add(content_negotiation_transform));
+````
+ */
+pub struct ContentNegotiation<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for ContentNegotiation<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let format = req
+			.headers()
+			.get(hyper::header::ACCEPT)
+			.and_then(|h| h.to_str().ok())
+			.map(Format::from_accept)
+			.unwrap_or(Format::Json);
+
+		RESPONSE_FORMAT.scope(format, self.inner.call(req))
+	}
+}
+
+pub fn content_negotiation_transform<S>(inner: S) -> ContentNegotiation<S>
+{
+	ContentNegotiation {
+		inner,
+	}
+}
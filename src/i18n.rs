@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+
+use crate::error::{server_err_owned, CoreErrorCodes};
+use crate::input_helper::bytes_to_json;
+use crate::res::AppRes;
+
+static CATALOG: OnceLock<MessageCatalog> = OnceLock::new();
+
+tokio::task_local! {
+	static LOCALES: Vec<String>;
+}
+
+/**
+# Translations of the error messages
+
+Every locale is a json file in a directory, e.g. `de.json` or `pt-BR.json`, with the error code as key:
+
+````json
+{
+	"12": "Die Eingabe ist zu groß",
+	"502": "Die Datei ist zu groß. Die maximale Größe ist: {max_chunk_size}"
+}
+````
+
+Parameters in `{}` are replaced by the msg params of the error.
+Errors without a translation for the locale keep the msg of the error.
+ */
+#[derive(Default)]
+pub struct MessageCatalog
+{
+	messages: HashMap<String, HashMap<u32, String>>,
+}
+
+impl MessageCatalog
+{
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+
+	pub fn load_dir(path: impl AsRef<Path>) -> AppRes<Self>
+	{
+		let mut catalog = Self::new();
+
+		for entry in fs::read_dir(path)? {
+			let path = entry?.path();
+
+			if path.extension().and_then(|e| e.to_str()) != Some("json") {
+				continue;
+			}
+
+			let locale = match path.file_stem().and_then(|s| s.to_str()) {
+				Some(l) => l.to_string(),
+				None => continue,
+			};
+
+			let messages: HashMap<String, String> = bytes_to_json(&fs::read(&path)?)?;
+
+			for (code, msg) in messages {
+				let code = code.parse().map_err(|_e| {
+					server_err_owned(
+						400,
+						CoreErrorCodes::JsonParse,
+						format!("Error code must be a number in: {:?}", path),
+						None,
+					)
+				})?;
+
+				catalog.add(&locale, code, msg);
+			}
+		}
+
+		Ok(catalog)
+	}
+
+	pub fn add(&mut self, locale: &str, error_code: u32, msg: String)
+	{
+		self.messages
+			.entry(locale.to_lowercase())
+			.or_default()
+			.insert(error_code, msg);
+	}
+
+	/**
+	Looks up the first locale with a translation. For `de-AT` the `de` translation is used too.
+	 */
+	pub fn translate(&self, locales: &[String], error_code: u32, params: &[(&'static str, String)]) -> Option<String>
+	{
+		for locale in locales {
+			let primary = locale.split('-').next().unwrap_or(locale);
+
+			let msg = self
+				.messages
+				.get(locale.as_str())
+				.and_then(|m| m.get(&error_code))
+				.or_else(|| self.messages.get(primary).and_then(|m| m.get(&error_code)));
+
+			if let Some(msg) = msg {
+				let mut msg = msg.clone();
+
+				for (name, value) in params {
+					msg = msg.replace(&format!("{{{}}}", name), value);
+				}
+
+				return Some(msg);
+			}
+		}
+
+		None
+	}
+}
+
+/**
+Sets the catalog for the whole app. Only the first catalog is used.
+ */
+pub fn set_catalog(catalog: MessageCatalog)
+{
+	let _ = CATALOG.set(catalog);
+}
+
+/**
+Translates the error message into the locale of the current request.
+The locale middleware is needed for the route.
+ */
+pub fn translate(error_code: u32, params: &[(&'static str, String)]) -> Option<String>
+{
+	let catalog = CATALOG.get()?;
+
+	LOCALES
+		.try_with(|locales| catalog.translate(locales, error_code, params))
+		.ok()
+		.flatten()
+}
+
+/**
+The locales of the accept language header, sorted by the quality. Locales with q=0 are not acceptable and not returned.
+ */
+pub fn parse_accept_language(header: &str) -> Vec<String>
+{
+	let mut locales: Vec<(String, f32)> = header
+		.split(',')
+		.filter_map(|part| {
+			let mut split = part.trim().split(';');
+
+			let locale = split.next()?.trim().to_lowercase();
+
+			if locale.is_empty() || locale == "*" {
+				return None;
+			}
+
+			let q = split
+				.find_map(|p| p.trim().strip_prefix("q="))
+				.and_then(|q| q.parse().ok())
+				.unwrap_or(1.0);
+
+			//q=0 means not acceptable
+			if q <= 0.0 {
+				return None;
+			}
+
+			Some((locale, q))
+		})
+		.collect();
+
+	//stable sort, so the order of the header is kept for the same quality
+	locales.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+	locales.into_iter().map(|(l, _)| l).collect()
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware to translate the error messages by the accept language header
+
+````ignore
+i18n::set_catalog(MessageCatalog::load_dir("./locales").unwrap());
+
+router.get("/api/user", r(user_handler).add(locale_transform));
+````
+ */
+pub struct Locale<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for Locale<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let locales = req
+			.headers()
+			.get(hyper::header::ACCEPT_LANGUAGE)
+			.and_then(|h| h.to_str().ok())
+			.map(parse_accept_language)
+			.unwrap_or_default();
+
+		LOCALES.scope(locales, self.inner.call(req))
+	}
+}
+
+pub fn locale_transform<S>(inner: S) -> Locale<S>
+{
+	Locale {
+		inner,
+	}
+}
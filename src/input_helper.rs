@@ -1,42 +1,158 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::OnceLock;
+
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder, ZlibDecoder};
 use bytes::BytesMut;
 use futures::StreamExt;
+use hyper::Body;
 use rustgram::Request;
+use serde::de::DeserializeOwned;
 use serde::{de, Serialize};
 use serde_json::{from_slice, to_string};
+use tokio_util::io::{ReaderStream, StreamReader};
 
-use crate::error::{server_err, server_err_owned, CoreErrorCodes};
+use crate::db::id_handling::create_id;
+use crate::error::{server_err, server_err_owned, CoreErrorCodes, FieldError, ResultContext, ServerCoreError, ServerErrorConstructor};
+use crate::file::FileHandler;
+use crate::format::get_body_format;
+use crate::log;
 use crate::res::AppRes;
+use crate::validate::Validate;
+use crate::value::Value;
+
+const MAX_SIZE_JSON: usize = 262_144; // default max payload size is 256k
+
+static MAX_BODY_SIZE: OnceLock<usize> = OnceLock::new();
+
+/**
+Sets the max body size for every route. Only the first call sets the size.
+
+Use the with_limit functions for routes with other limits, e.g. for bigger inputs.
+ */
+pub fn set_max_body_size(size: usize)
+{
+	let _ = MAX_BODY_SIZE.set(size);
+}
+
+pub fn max_body_size() -> usize
+{
+	*MAX_BODY_SIZE.get().unwrap_or(&MAX_SIZE_JSON)
+}
 
-const MAX_SIZE_JSON: usize = 262_144; // max payload size is 256k
+fn input_too_big() -> ServerCoreError
+{
+	server_err(413, CoreErrorCodes::InputTooBig, "Input was too big to handle")
+}
 
 pub async fn get_raw_body(req: &mut Request) -> AppRes<BytesMut>
 {
+	get_raw_body_with_limit(req, max_body_size()).await
+}
+
+pub async fn get_raw_body_with_limit(req: &mut Request, limit: usize) -> AppRes<BytesMut>
+{
+	//reject before reading when the client already tells us the size
+	let content_length = req
+		.headers()
+		.get(hyper::header::CONTENT_LENGTH)
+		.and_then(|h| h.to_str().ok())
+		.and_then(|h| h.parse::<usize>().ok());
+
+	if let Some(len) = content_length {
+		if len > limit {
+			return Err(input_too_big());
+		}
+	}
+
+	//the limit is checked for the decompressed body, so compression bombs are rejected too
+	let mut req_body = decode_body(req)?;
+
 	//read the json to memory
-	let req_body = req.body_mut();
-	let mut body = BytesMut::new();
+	let mut body = BytesMut::with_capacity(content_length.unwrap_or(0));
 
 	while let Some(bytes) = req_body.next().await {
-		match bytes {
-			Ok(chunk) => {
-				if (body.len() + chunk.len()) > MAX_SIZE_JSON {
-					return Err(server_err(
-						413,
-						CoreErrorCodes::InputTooBig,
-						"Input was too big to handle",
-					));
-				}
-
-				body.extend_from_slice(&chunk);
-			},
-			Err(_) => {
-				continue;
-			},
+		//don't parse a truncated body
+		let chunk = bytes.context(400, CoreErrorCodes::InputRead, "Can't read the input")?;
+
+		if (body.len() + chunk.len()) > limit {
+			return Err(input_too_big());
 		}
+
+		body.extend_from_slice(&chunk);
 	}
 
 	Ok(body)
 }
 
+/**
+Reads the body in the format of the content type: json, MessagePack or CBOR
+ */
+pub async fn get_body<T: DeserializeOwned>(req: &mut Request) -> AppRes<T>
+{
+	get_body_with_limit(req, max_body_size()).await
+}
+
+pub async fn get_body_with_limit<T: DeserializeOwned>(req: &mut Request, limit: usize) -> AppRes<T>
+{
+	let format = get_body_format(req)?;
+
+	let body = get_raw_body_with_limit(req, limit).await?;
+
+	format.deserialize(&body)
+}
+
+/**
+Takes the body of the request and decompresses it by the content encoding.
+
+Supported are gzip, deflate and br. The body is decompressed while it is streamed.
+ */
+pub fn decode_body(req: &mut Request) -> AppRes<Body>
+{
+	let encoding = req
+		.headers()
+		.get(hyper::header::CONTENT_ENCODING)
+		.and_then(|h| h.to_str().ok())
+		.map(|h| h.trim().to_lowercase());
+
+	let body = std::mem::take(req.body_mut());
+
+	let encoding = match encoding.as_deref() {
+		None | Some("") | Some("identity") => return Ok(body),
+		Some(e) => e.to_string(),
+	};
+
+	let reader = StreamReader::new(body.map(|chunk| chunk.map_err(io::Error::other)));
+
+	let body = match encoding.as_str() {
+		"gzip" | "x-gzip" => Body::wrap_stream(ReaderStream::new(GzipDecoder::new(reader))),
+		"deflate" => Body::wrap_stream(ReaderStream::new(ZlibDecoder::new(reader))),
+		"br" => Body::wrap_stream(ReaderStream::new(BrotliDecoder::new(reader))),
+		_ => {
+			return Err(server_err_owned(
+				415,
+				CoreErrorCodes::UnsupportedEncoding,
+				format!("Content encoding is not supported: {}", encoding),
+				None,
+			));
+		},
+	};
+
+	Ok(body)
+}
+
+pub async fn get_json_body<T: DeserializeOwned>(req: &mut Request) -> AppRes<T>
+{
+	get_json_body_with_limit(req, max_body_size()).await
+}
+
+pub async fn get_json_body_with_limit<T: DeserializeOwned>(req: &mut Request, limit: usize) -> AppRes<T>
+{
+	let body = get_raw_body_with_limit(req, limit).await?;
+
+	bytes_to_json(&body)
+}
+
 pub fn json_to_string<T>(value: &T) -> AppRes<String>
 where
 	T: ?Sized + Serialize,
@@ -58,17 +174,27 @@ pub fn bytes_to_json<'a, T>(v: &'a [u8]) -> AppRes<T>
 where
 	T: de::Deserialize<'a>,
 {
-	match from_slice::<T>(v) {
-		Ok(o) => Ok(o),
-		Err(e) => {
-			Err(server_err_owned(
-				422,
-				CoreErrorCodes::JsonParse,
-				format!("Wrong input: {:?}", e),
-				None,
-			))
-		},
-	}
+	from_slice::<T>(v).map_err(|e| input_err("json", e))
+}
+
+/**
+Parses the json and validates it. Validation errors are returned as 422 with every field error.
+ */
+pub fn bytes_to_validated_json<'a, T>(v: &'a [u8]) -> AppRes<T>
+where
+	T: de::Deserialize<'a> + Validate,
+{
+	let value: T = bytes_to_json(v)?;
+
+	value.validate()?;
+
+	Ok(value)
+}
+
+pub(crate) fn input_err(format: &'static str, e: impl std::fmt::Display) -> ServerCoreError
+{
+	//only the reason for the client, not the debug output of serde. without a field because serde doesn't give the path
+	ServerCoreError::new_msg(422, CoreErrorCodes::JsonParse, "Wrong input").with_errors(vec![FieldError::new("", format, e.to_string())])
 }
 
 pub fn qs_to_string<T: Serialize>(value: &T) -> AppRes<String>
@@ -85,5 +211,185 @@ pub fn qs_to_string<T: Serialize>(value: &T) -> AppRes<String>
 
 pub fn bytes_to_qs<'a, T: de::Deserialize<'a>>(v: &'a &[u8]) -> AppRes<T>
 {
-	serde_qs::from_bytes(v).map_err(|e| server_err_owned(422, CoreErrorCodes::JsonParse, format!("Wrong input: {:?}", e), None))
+	serde_qs::from_bytes(v).map_err(|e| input_err("qs", e))
+}
+
+//__________________________________________________________________________________________________
+//multipart
+
+pub struct MultipartLimits
+{
+	pub max_file_size: usize,
+	pub max_field_size: usize,
+	pub max_total_size: usize,
+}
+
+impl Default for MultipartLimits
+{
+	fn default() -> Self
+	{
+		Self {
+			max_file_size: 10 * 1024 * 1024,
+			max_field_size: MAX_SIZE_JSON,
+			max_total_size: 50 * 1024 * 1024,
+		}
+	}
+}
+
+pub struct MultipartFile
+{
+	pub field: String,
+	pub file_name: Option<String>,
+	pub content_type: Option<String>,
+	pub part_id: String,
+	pub size: usize,
+}
+
+pub struct MultipartData
+{
+	pub fields: Value,
+	pub files: Vec<MultipartFile>,
+}
+
+/**
+# Reads multipart/form-data
+
+Text fields are collected into a Value::Object, fields with the same name are put into an array.
+Every file is streamed to the file handler with a new part id, so files are never kept in memory.
+
+When something went wrong, the already uploaded files are deleted.
+
+````ignore
+let data = get_multipart(req, &LocalStorage::new("./files".to_string()), &MultipartLimits::default()).await?;
+````
+ */
+pub async fn get_multipart(mut req: Request, file_handler: &dyn FileHandler, limits: &MultipartLimits) -> AppRes<MultipartData>
+{
+	let boundary = req
+		.headers()
+		.get(hyper::header::CONTENT_TYPE)
+		.and_then(|h| h.to_str().ok())
+		.and_then(|h| multer::parse_boundary(h).ok())
+		.ok_or_else(|| server_err(400, CoreErrorCodes::MultipartParse, "No multipart boundary sent"))?;
+
+	let mut files: Vec<MultipartFile> = Vec::new();
+
+	//every part id is kept before the upload starts, so a part which failed in the middle is deleted too
+	let mut parts: Vec<String> = Vec::new();
+
+	let body = decode_body(&mut req)?;
+
+	match read_multipart(body, boundary, file_handler, limits, &mut files, &mut parts).await {
+		Ok(fields) => {
+			Ok(MultipartData {
+				fields: Value::Object(fields),
+				files,
+			})
+		},
+		Err(e) => {
+			//return the original error and not the one of the cleanup
+			if let Err(delete_err) = file_handler.delete_parts(&parts).await {
+				log::error(&format!(
+					"multipart parts not deleted: {}",
+					delete_err.msg_owned.as_deref().unwrap_or(delete_err.msg)
+				));
+			}
+
+			Err(e)
+		},
+	}
+}
+
+async fn read_multipart(
+	body: Body,
+	boundary: String,
+	file_handler: &dyn FileHandler,
+	limits: &MultipartLimits,
+	files: &mut Vec<MultipartFile>,
+	parts: &mut Vec<String>,
+) -> AppRes<BTreeMap<String, Value>>
+{
+	let mut multipart = multer::Multipart::new(body, boundary);
+	let mut fields: BTreeMap<String, Value> = BTreeMap::new();
+	let mut total_size: usize = 0;
+
+	while let Some(mut field) = multipart.next_field().await.map_err(multipart_err)? {
+		let name = field.name().unwrap_or_default().to_string();
+
+		if let Some(file_name) = field.file_name().map(|n| n.to_string()) {
+			let content_type = field.content_type().map(|c| c.to_string());
+			let part_id = create_id();
+			parts.push(part_id.clone());
+
+			let max_size = limits
+				.max_file_size
+				.min(limits.max_total_size.saturating_sub(total_size));
+
+			//pass the field as body to the handler
+			let mut builder = hyper::Request::builder();
+
+			if let Some(c) = &content_type {
+				builder = builder.header(hyper::header::CONTENT_TYPE, c);
+			}
+
+			let file_req = builder
+				.body(Body::wrap_stream(field))
+				.map_err(|_e| server_err(400, CoreErrorCodes::MultipartParse, "Can't read the multipart input"))?;
+
+			let size = file_handler
+				.upload_part(file_req, &part_id, max_size)
+				.await?;
+
+			total_size += size;
+
+			files.push(MultipartFile {
+				field: name,
+				file_name: Some(file_name),
+				content_type,
+				part_id,
+				size,
+			});
+
+			continue;
+		}
+
+		let mut text = BytesMut::new();
+
+		while let Some(chunk) = field.chunk().await.map_err(multipart_err)? {
+			total_size += chunk.len();
+
+			if text.len() + chunk.len() > limits.max_field_size || total_size > limits.max_total_size {
+				return Err(input_too_big());
+			}
+
+			text.extend_from_slice(&chunk);
+		}
+
+		let text = String::from_utf8(text.to_vec()).map_err(|_e| input_err("multipart", format!("Field {} is not utf-8", name)))?;
+
+		match fields.remove(&name) {
+			Some(Value::Array(mut values)) => {
+				values.push(Value::String(text));
+				fields.insert(name, Value::Array(values));
+			},
+			Some(value) => {
+				fields.insert(name, Value::Array(vec![value, Value::String(text)]));
+			},
+			None => {
+				fields.insert(name, Value::String(text));
+			},
+		}
+	}
+
+	Ok(fields)
+}
+
+fn multipart_err(e: multer::Error) -> ServerCoreError
+{
+	server_err_owned(
+		400,
+		CoreErrorCodes::MultipartParse,
+		"Can't read the multipart input".to_string(),
+		Some(e.to_string()),
+	)
 }
@@ -5,15 +5,27 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use rustgram::{Request, Response};
 
 pub mod cache;
+pub mod compression;
+pub mod conditional;
+pub mod cookie;
+pub mod cors;
 pub mod db;
 pub mod error;
 pub mod file;
+pub mod format;
+pub mod i18n;
 pub mod input_helper;
+pub mod log;
+pub mod problem;
+pub mod request_id;
 pub mod res;
+pub mod security_headers;
 pub mod simple_static_server;
+pub mod sse;
 #[cfg(feature = "static_var")]
 pub mod static_var;
 pub mod url_helper;
+pub mod validate;
 pub mod value;
 
 pub fn get_time() -> res::AppRes<u128>
@@ -61,22 +73,8 @@ pub use rustgram_server_util_macros::Sqlite as DB;
 pub use rustgram_server_util_macros::*;
 
 /// A generic cors response handler for option routes.
-/// It is useful for api that can be accessed by any client
-pub async fn cors_handler(_req: Request) -> Response
+/// It uses the config of `cors::set_cors_config`, the `cors::cors_transform` middleware handles the preflight without this handler.
+pub async fn cors_handler(req: Request) -> Response
 {
-	hyper::Response::builder()
-		.header("Content-Length", "0")
-		.header(
-			"Access-Control-Allow-Methods",
-			"GET, POST, PUT, DELETE, OPTIONS, PATCH",
-		)
-		.header("Access-Control-Max-Age", "86400")
-		.header("Access-Control-Allow-Origin", "*")
-		.header("Access-Control-Allow-Credentials", "true")
-		.header(
-			"Access-Control-Allow-Headers",
-			"x-sentc-app-token, x-sentc-group-access-id, Content-Type, Accept, Origin, Authorization, x-socket-id",
-		)
-		.body(hyper::Body::from(""))
-		.unwrap()
+	cors::cors_config().preflight_response(&req)
 }
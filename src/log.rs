@@ -0,0 +1,245 @@
+use std::env;
+use std::future::Future;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use serde::Serialize;
+
+use crate::get_time;
+use crate::input_helper::json_to_string;
+use crate::request_id::current_request_id;
+
+//atomic and lock instead of once cells, so the logger and the level can be set after the first log output
+static LOGGER: RwLock<Option<Box<dyn Logger>>> = RwLock::new(None);
+static LEVEL: AtomicU8 = AtomicU8::new(LEVEL_UNSET);
+
+const LEVEL_UNSET: u8 = u8::MAX;
+
+tokio::task_local! {
+	static REQUEST_PATH: String;
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Level
+{
+	Debug,
+	Info,
+	Warn,
+	Error,
+}
+
+impl Level
+{
+	fn from_env() -> Self
+	{
+		match env::var("LOG_LEVEL").as_deref() {
+			Ok("debug") => Self::Debug,
+			Ok("info") => Self::Info,
+			Ok("warn") => Self::Warn,
+			Ok("error") => Self::Error,
+			//keep the init output of the debug builds
+			_ if cfg!(debug_assertions) => Self::Debug,
+			_ => Self::Info,
+		}
+	}
+
+	fn from_u8(level: u8) -> Self
+	{
+		match level {
+			0 => Self::Debug,
+			1 => Self::Info,
+			2 => Self::Warn,
+			_ => Self::Error,
+		}
+	}
+}
+
+#[derive(Serialize)]
+pub struct LogEntry<'a>
+{
+	pub level: Level,
+	/// Time in milliseconds
+	pub time: u128,
+	pub msg: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error_code: Option<u32>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub http_status: Option<u16>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub path: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub request_id: Option<String>,
+}
+
+/**
+# Log sink
+
+Implement this to send the logs to a 3rd party service. The default logger prints json lines to std out.
+ */
+pub trait Logger: Send + Sync
+{
+	fn log(&self, entry: &LogEntry);
+}
+
+pub struct JsonLogger;
+
+impl Logger for JsonLogger
+{
+	fn log(&self, entry: &LogEntry)
+	{
+		if let Ok(line) = json_to_string(entry) {
+			println!("{}", line);
+		}
+	}
+}
+
+/**
+Sets the logger for the whole app.
+
+Call this before the first log output (e.g. before the db or cache init), the output before goes to the default logger.
+ */
+pub fn set_logger(logger: Box<dyn Logger>)
+{
+	*LOGGER.write().unwrap_or_else(|e| e.into_inner()) = Some(logger);
+}
+
+/**
+Sets the min log level. The default is read from the `LOG_LEVEL` env (debug, info, warn or error).
+
+Call this before the first log output, the output before uses the default level.
+ */
+pub fn set_level(level: Level)
+{
+	LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool
+{
+	let min = match LEVEL.load(Ordering::Relaxed) {
+		LEVEL_UNSET => {
+			let min = Level::from_env();
+
+			//don't overwrite a level which was set in the meantime
+			let _ = LEVEL.compare_exchange(LEVEL_UNSET, min as u8, Ordering::Relaxed, Ordering::Relaxed);
+
+			min
+		},
+		l => Level::from_u8(l),
+	};
+
+	level >= min
+}
+
+pub fn log(entry: &LogEntry)
+{
+	if !enabled(entry.level) {
+		return;
+	}
+
+	match LOGGER.read().unwrap_or_else(|e| e.into_inner()).as_deref() {
+		Some(logger) => logger.log(entry),
+		None => JsonLogger.log(entry),
+	}
+}
+
+fn log_msg(level: Level, msg: &str)
+{
+	if !enabled(level) {
+		return;
+	}
+
+	log(&LogEntry {
+		level,
+		time: get_time().unwrap_or(0),
+		msg,
+		error_code: None,
+		http_status: None,
+		path: current_path(),
+		request_id: current_request_id(),
+	});
+}
+
+pub fn debug(msg: &str)
+{
+	log_msg(Level::Debug, msg)
+}
+
+pub fn info(msg: &str)
+{
+	log_msg(Level::Info, msg)
+}
+
+pub fn warn(msg: &str)
+{
+	log_msg(Level::Warn, msg)
+}
+
+pub fn error(msg: &str)
+{
+	log_msg(Level::Error, msg)
+}
+
+pub(crate) fn log_http_error(level: Level, http_status: u16, error_code: u32, msg: &str)
+{
+	if !enabled(level) {
+		return;
+	}
+
+	log(&LogEntry {
+		level,
+		time: get_time().unwrap_or(0),
+		msg,
+		error_code: Some(error_code),
+		http_status: Some(http_status),
+		path: current_path(),
+		request_id: current_request_id(),
+	});
+}
+
+/**
+The path of the current request, if the log context middleware is used for the route
+ */
+pub fn current_path() -> Option<String>
+{
+	REQUEST_PATH.try_with(|p| p.clone()).ok()
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware to add the request path to the logs
+
+Every log output while handling the request (including the error responses) contains the path.
+
+````ignore
+router.get("/api/user", r(user_handler).add(log_context_transform));
+````
+ */
+pub struct LogContext<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for LogContext<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let path = req.uri().path().to_string();
+
+		REQUEST_PATH.scope(path, self.inner.call(req))
+	}
+}
+
+pub fn log_context_transform<S>(inner: S) -> LogContext<S>
+{
+	LogContext {
+		inner,
+	}
+}
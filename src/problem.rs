@@ -0,0 +1,165 @@
+use std::future::Future;
+use std::sync::OnceLock;
+
+use hyper::StatusCode;
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use serde::Serialize;
+
+use crate::error::FieldError;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+type ProblemTypeMapper = Box<dyn Fn(u32) -> Option<String> + Send + Sync>;
+
+static ERROR_FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+static TYPE_MAPPER: OnceLock<ProblemTypeMapper> = OnceLock::new();
+
+tokio::task_local! {
+	static ACCEPT_PROBLEM: bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat
+{
+	/// The ServerOutput json envelope for every error
+	Default,
+	/// problem+json for every error
+	Problem,
+	/// problem+json only if the client accepts it. The problem negotiation middleware is needed for the route.
+	Negotiate,
+}
+
+/**
+# Problem details for http apis (RFC 7807)
+
+The err_code and the field errors are added as extension members.
+ */
+#[derive(Serialize)]
+pub struct ProblemDetails<'a>
+{
+	#[serde(rename = "type")]
+	pub problem_type: String,
+	pub title: &'a str,
+	pub status: u16,
+	pub detail: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub instance: Option<String>,
+	pub err_code: u32,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub request_id: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub errors: Option<Vec<FieldError>>,
+}
+
+impl<'a> ProblemDetails<'a>
+{
+	pub fn new(status: StatusCode, error_code: u32, detail: &'a str) -> Self
+	{
+		let problem_type = TYPE_MAPPER
+			.get()
+			.and_then(|m| m(error_code))
+			.unwrap_or_else(|| "about:blank".to_string());
+
+		Self {
+			problem_type,
+			title: status.canonical_reason().unwrap_or("Error"),
+			status: status.as_u16(),
+			detail,
+			instance: None,
+			err_code: error_code,
+			request_id: None,
+			errors: None,
+		}
+	}
+}
+
+/**
+Sets the format of the error responses. The default is the ServerOutput json.
+
+Only the first call sets the format.
+ */
+pub fn set_error_format(format: ErrorFormat)
+{
+	let _ = ERROR_FORMAT.set(format);
+}
+
+/**
+Sets the mapping from the error code to the problem type uri, e.g. `https://example.com/errors/21`.
+
+Codes without a mapping get the type `about:blank`.
+
+````ignore
+set_problem_type_mapper(Box::new(|code| Some(format!("https://example.com/errors/{}", code))));
+````
+ */
+pub fn set_problem_type_mapper(mapper: ProblemTypeMapper)
+{
+	let _ = TYPE_MAPPER.set(mapper);
+}
+
+pub fn use_problem_format() -> bool
+{
+	match ERROR_FORMAT.get().unwrap_or(&ErrorFormat::Default) {
+		ErrorFormat::Default => false,
+		ErrorFormat::Problem => true,
+		ErrorFormat::Negotiate => ACCEPT_PROBLEM.try_with(|a| *a).unwrap_or(false),
+	}
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware to choose the error format by the accept header
+
+Only used with the `ErrorFormat::Negotiate`.
+ */
+pub struct ProblemNegotiation<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for ProblemNegotiation<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let accept = req
+			.headers()
+			.get_all(hyper::header::ACCEPT)
+			.iter()
+			.filter_map(|h| h.to_str().ok())
+			.any(accepts_problem);
+
+		ACCEPT_PROBLEM.scope(accept, self.inner.call(req))
+	}
+}
+
+fn accepts_problem(header: &str) -> bool
+{
+	header.split(',').any(|part| {
+		let mut split = part.trim().split(';');
+
+		if split.next().map(|m| m.trim().to_lowercase()).as_deref() != Some(PROBLEM_CONTENT_TYPE) {
+			return false;
+		}
+
+		let q: f32 = split
+			.find_map(|p| p.trim().strip_prefix("q="))
+			.and_then(|q| q.parse().ok())
+			.unwrap_or(1.0);
+
+		//q=0 means not acceptable
+		q > 0.0
+	})
+}
+
+pub fn problem_negotiation_transform<S>(inner: S) -> ProblemNegotiation<S>
+{
+	ProblemNegotiation {
+		inner,
+	}
+}
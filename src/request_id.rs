@@ -0,0 +1,101 @@
+use std::future::Future;
+
+use hyper::header::HeaderValue;
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+
+use crate::db::id_handling::create_id;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+	static REQUEST_ID: String;
+}
+
+/**
+The id of the request in the request extensions
+ */
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+/**
+The id of the current request, if the request id middleware is used for the route.
+
+This is used by the responses and the logs, so they don't need the request.
+ */
+pub fn current_request_id() -> Option<String>
+{
+	REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+pub fn get_request_id(req: &Request) -> Option<&str>
+{
+	req.extensions().get::<RequestId>().map(|id| id.0.as_str())
+}
+
+fn is_valid_id(id: &str) -> bool
+{
+	!id.is_empty() &&
+		id.len() <= MAX_REQUEST_ID_LEN &&
+		id.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/**
+# Middleware for request correlation ids
+
+Takes the id from the `X-Request-Id` header (e.g. set by a load balancer) or creates a new one.
+The id is sent back in the `X-Request-Id` header of every response,
+is added to the json error body as `request_id` and is part of every log output.
+
+````ignore
+router.get("/api/user", r(user_handler).add(request_id_transform));
+````
+ */
+pub struct RequestIdMiddleware<S>
+{
+	inner: S,
+}
+
+impl<S> Service<Request> for RequestIdMiddleware<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, mut req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let id = match req
+			.headers()
+			.get(REQUEST_ID_HEADER)
+			.and_then(|h| h.to_str().ok())
+		{
+			Some(id) if is_valid_id(id) => id.to_string(),
+			_ => create_id(),
+		};
+
+		req.extensions_mut().insert(RequestId(id.clone()));
+
+		let next = self.inner.call(req);
+
+		REQUEST_ID.scope(id.clone(), async move {
+			let mut res = next.await;
+
+			//for responses which are not created by this crate
+			if let Ok(v) = HeaderValue::from_str(&id) {
+				res.headers_mut().entry(REQUEST_ID_HEADER).or_insert(v);
+			}
+
+			res
+		})
+	}
+}
+
+pub fn request_id_transform<S>(inner: S) -> RequestIdMiddleware<S>
+{
+	RequestIdMiddleware {
+		inner,
+	}
+}
@@ -1,16 +1,27 @@
+use std::time::SystemTime;
+
 use hyper::StatusCode;
 use rustgram::service::IntoResponse;
 use rustgram::Response;
 use serde::Serialize;
 
-use crate::error::ServerCoreError;
-use crate::get_time;
+use crate::conditional::{date_time_to_system_time, etag_from_body, etag_from_version, etag_matches, http_date, not_modified_since};
+use crate::cookie::Cookie;
+use crate::db::custom_types::date_str::DateTimeStr;
+use crate::error::{server_err_owned, CoreErrorCodes, FieldError, ServerCoreError};
+use crate::format::{current_format, Format};
+use crate::i18n::translate;
 use crate::input_helper::json_to_string;
+use crate::log::{current_path, log_http_error, Level};
+use crate::problem::{use_problem_format, ProblemDetails, PROBLEM_CONTENT_TYPE};
+use crate::request_id::{current_request_id, REQUEST_ID_HEADER};
 
 //__________________________________________________________________________________________________
 //two different output for str msg and string msg but for the client is always the same
+//non_exhaustive because new fields are added to the envelope, use the constructors outside this crate
 
 #[derive(Serialize)]
+#[non_exhaustive]
 pub struct ServerOutputStr<T>
 {
 	pub status: bool,
@@ -19,10 +30,15 @@ pub struct ServerOutputStr<T>
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub err_code: Option<u32>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	pub errors: Option<Vec<FieldError>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
 	pub result: Option<T>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub request_id: Option<String>,
 }
 
 #[derive(Serialize)]
+#[non_exhaustive]
 pub struct ServerOutput<T>
 {
 	pub status: bool,
@@ -31,7 +47,65 @@ pub struct ServerOutput<T>
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub err_code: Option<u32>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	pub errors: Option<Vec<FieldError>>,
+	#[serde(skip_serializing_if = "Option::is_none")]
 	pub result: Option<T>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub request_id: Option<String>,
+}
+
+impl<T> ServerOutputStr<T>
+{
+	pub fn success(result: T) -> Self
+	{
+		Self {
+			status: true,
+			err_msg: None,
+			err_code: None,
+			errors: None,
+			result: Some(result),
+			request_id: None,
+		}
+	}
+
+	pub fn error(err_msg: &'static str, err_code: u32) -> Self
+	{
+		Self {
+			status: false,
+			err_msg: Some(err_msg),
+			err_code: Some(err_code),
+			errors: None,
+			result: None,
+			request_id: None,
+		}
+	}
+}
+
+impl<T> ServerOutput<T>
+{
+	pub fn success(result: T) -> Self
+	{
+		Self {
+			status: true,
+			err_msg: None,
+			err_code: None,
+			errors: None,
+			result: Some(result),
+			request_id: None,
+		}
+	}
+
+	pub fn error(err_msg: String, err_code: u32) -> Self
+	{
+		Self {
+			status: false,
+			err_msg: Some(err_msg),
+			err_code: Some(err_code),
+			errors: None,
+			result: None,
+			request_id: None,
+		}
+	}
 }
 
 //__________________________________________________________________________________________________
@@ -43,36 +117,76 @@ impl IntoResponse<Response> for ServerCoreError
 		let status = StatusCode::from_u16(self.http_status_code).unwrap_or(StatusCode::BAD_REQUEST);
 
 		//msg for the developer only
-		//for std out to get logged with 3rd party service.
-		if let Some(m) = self.debug_msg {
-			let time = get_time().unwrap_or(0);
-			println!("Http Error at time: {} Error: {:?}", time, m);
+		//for the log sink to get logged with 3rd party service.
+		match &self.debug_msg {
+			Some(m) => log_http_error(Level::Error, self.http_status_code, self.error_code, m),
+			None => {
+				log_http_error(
+					Level::Debug,
+					self.http_status_code,
+					self.error_code,
+					self.msg_owned.as_deref().unwrap_or(self.msg),
+				)
+			},
 		}
 
-		let body = if let Some(m) = self.msg_owned {
-			json_to_string(&ServerOutput::<String> {
-				status: false,
-				result: None,
-				err_msg: Some(m),
-				err_code: Some(self.error_code),
-			})
-			.unwrap()
+		let request_id = current_request_id();
+
+		//the translated msg for the client, the log keeps the original msg
+		let msg_owned = translate(self.error_code, &self.msg_params).or(self.msg_owned);
+
+		let errors = if self.errors.is_empty() { None } else { Some(self.errors.into_vec()) };
+
+		let (body, content_type) = if use_problem_format() {
+			let detail = msg_owned.as_deref().unwrap_or(self.msg);
+
+			let mut problem = ProblemDetails::new(status, self.error_code, detail);
+			problem.instance = current_path();
+			problem.request_id = request_id.clone();
+			problem.errors = errors;
+
+			(json_to_string(&problem).unwrap().into_bytes(), PROBLEM_CONTENT_TYPE)
+		} else if let Some(m) = msg_owned {
+			let format = current_format();
+
+			let body = format
+				.serialize(&ServerOutput::<String> {
+					status: false,
+					result: None,
+					err_msg: Some(m),
+					err_code: Some(self.error_code),
+					errors,
+					request_id: request_id.clone(),
+				})
+				.unwrap();
+
+			(body, format.content_type())
 		} else {
-			json_to_string(&ServerOutputStr::<String> {
-				status: false,
-				result: None,
-				err_msg: Some(self.msg),
-				err_code: Some(self.error_code),
-			})
-			.unwrap()
+			let format = current_format();
+
+			let body = format
+				.serialize(&ServerOutputStr::<String> {
+					status: false,
+					result: None,
+					err_msg: Some(self.msg),
+					err_code: Some(self.error_code),
+					errors,
+					request_id: request_id.clone(),
+				})
+				.unwrap();
+
+			(body, format.content_type())
 		};
 
-		hyper::Response::builder()
+		let mut builder = hyper::Response::builder()
 			.status(status)
-			.header("Content-Type", "application/json")
-			.header("Access-Control-Allow-Origin", "*")
-			.body(hyper::Body::from(body))
-			.unwrap()
+			.header("Content-Type", content_type);
+
+		if let Some(id) = request_id {
+			builder = builder.header(REQUEST_ID_HEADER, id);
+		}
+
+		builder.body(hyper::Body::from(body)).unwrap()
 	}
 }
 
@@ -89,23 +203,263 @@ impl<T: Serialize> IntoResponse<Response> for JsonRes<T>
 {
 	fn into_response(self) -> Response
 	{
-		let out = ServerOutput {
-			status: true,
-			err_msg: None,
-			err_code: None,
-			result: Some(self.0),
+		success_response(self.0, Format::Json, StatusCode::OK, Vec::new())
+	}
+}
+
+impl<T: Serialize> JsonRes<T>
+{
+	/**
+	Responds with another status, e.g. 201 Created. For 204 No Content the body is empty.
+
+	Only for 2xx and 3xx, the body is always a success output. Errors are returned as ServerCoreError.
+	 */
+	pub fn with_status(self, status: StatusCode) -> JsonResBuilder<T>
+	{
+		JsonResBuilder::new(self.0).with_status(status)
+	}
+
+	pub fn header(self, name: &'static str, value: impl Into<String>) -> JsonResBuilder<T>
+	{
+		JsonResBuilder::new(self.0).header(name, value)
+	}
+
+	pub fn cookie(self, cookie: Cookie) -> JsonResBuilder<T>
+	{
+		JsonResBuilder::new(self.0).cookie(cookie)
+	}
+
+	/**
+	Sets a strong etag from the hash of the body
+	 */
+	pub fn etag(self) -> JsonResBuilder<T>
+	{
+		JsonResBuilder::new(self.0).etag()
+	}
+
+	/**
+	Sets the etag from a version, e.g. a version column. The body is not hashed.
+	 */
+	pub fn with_etag(self, version: impl std::fmt::Display) -> JsonResBuilder<T>
+	{
+		JsonResBuilder::new(self.0).with_etag(version)
+	}
+
+	pub fn last_modified(self, date_time: &DateTimeStr) -> JsonResBuilder<T>
+	{
+		JsonResBuilder::new(self.0).last_modified(date_time)
+	}
+}
+
+enum ETag
+{
+	Hash,
+	Version(String),
+}
+
+/**
+# JsonRes with status, headers and cookies
+
+````ignore
+async fn create_user(req: Request) -> JBRes<User>
+{
+	let user = ...;
+
+	Ok(JsonRes(user)
+		.with_status(StatusCode::CREATED)
+		.header("Location", format!("/api/user/{}", id))
+		.cookie(Cookie::new("session", token).http_only().secure()))
+}
+````
+
+With an etag or last modified, GET requests are answered with 304 Not Modified if the client has the current version.
+The conditional get middleware is needed for the route.
+ */
+pub struct JsonResBuilder<T: Serialize>
+{
+	result: T,
+	status: StatusCode,
+	headers: Vec<(&'static str, String)>,
+	cookies: Vec<Cookie>,
+	etag: Option<ETag>,
+	last_modified: Option<SystemTime>,
+}
+
+impl<T: Serialize> JsonResBuilder<T>
+{
+	pub fn new(result: T) -> Self
+	{
+		Self {
+			result,
+			status: StatusCode::OK,
+			headers: Vec::new(),
+			cookies: Vec::new(),
+			etag: None,
+			last_modified: None,
+		}
+	}
+
+	/**
+	Only for 2xx and 3xx, see JsonRes::with_status
+	 */
+	pub fn with_status(mut self, status: StatusCode) -> Self
+	{
+		self.status = status;
+		self
+	}
+
+	pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self
+	{
+		self.headers.push((name, value.into()));
+		self
+	}
+
+	pub fn cookie(mut self, cookie: Cookie) -> Self
+	{
+		self.cookies.push(cookie);
+		self
+	}
+
+	pub fn etag(mut self) -> Self
+	{
+		self.etag = Some(ETag::Hash);
+		self
+	}
+
+	pub fn with_etag(mut self, version: impl std::fmt::Display) -> Self
+	{
+		self.etag = Some(ETag::Version(etag_from_version(version)));
+		self
+	}
+
+	pub fn last_modified(mut self, date_time: &DateTimeStr) -> Self
+	{
+		self.last_modified = Some(date_time_to_system_time(date_time));
+		self
+	}
+}
+
+impl<T: Serialize> IntoResponse<Response> for JsonResBuilder<T>
+{
+	fn into_response(self) -> Response
+	{
+		//conditional requests only for 200, a 201 Created or a redirect must not become a 304
+		let conditional = self.status == StatusCode::OK;
+
+		//the hash needs the serialized body, a version is checked before serializing the body
+		let (etag, body) = match &self.etag {
+			Some(ETag::Version(v)) => (Some(v.clone()), None),
+			Some(ETag::Hash) => {
+				let body = match Format::Json.serialize(&ServerOutput::success(&self.result)) {
+					Ok(b) => b,
+					Err(e) => return e.into_response(),
+				};
+
+				(Some(etag_from_body(&body)), Some(body))
+			},
+			None => (None, None),
 		};
 
-		let string = match json_to_string(&out) {
+		//If-Modified-Since is only used without If-None-Match
+		let not_modified = conditional && (etag.as_deref().is_some_and(etag_matches) || self.last_modified.is_some_and(not_modified_since));
+
+		let mut headers = self.headers;
+
+		for cookie in self.cookies {
+			//a cookie name from the request data must not break the header
+			if let Err(e) = cookie.check() {
+				return e.into_response();
+			}
+
+			headers.push(("Set-Cookie", cookie.to_string()));
+		}
+
+		//the 304 must have the same validators as the 200
+		if let Some(last_modified) = self.last_modified {
+			headers.push(("Last-Modified", http_date(last_modified)));
+		}
+
+		headers.extend(etag.map(|e| ("ETag", e)));
+
+		let status = if not_modified { StatusCode::NOT_MODIFIED } else { self.status };
+
+		match body {
+			//the body is already serialized for the hash
+			Some(body) => body_response(body, Format::Json, status, headers),
+			None => success_response(self.result, Format::Json, status, headers),
+		}
+	}
+}
+
+/**
+Like JsonRes but in the format of the accept header: json, MessagePack or CBOR.
+
+The content negotiation middleware is needed for the route, without it json is used.
+ */
+pub struct Res<T: Serialize>(pub T);
+
+impl<T: Serialize> IntoResponse<Response> for Res<T>
+{
+	fn into_response(self) -> Response
+	{
+		success_response(self.0, current_format(), StatusCode::OK, Vec::new())
+	}
+}
+
+fn success_response<T: Serialize>(result: T, format: Format, status: StatusCode, headers: Vec<(&'static str, String)>) -> Response
+{
+	//no body allowed for these
+	let body = if status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+		Vec::new()
+	} else {
+		match format.serialize(&ServerOutput::success(result)) {
 			Ok(s) => s,
-			Err(e) => return Into::<ServerCoreError>::into(e).into_response(),
-		};
+			Err(e) => return e.into_response(),
+		}
+	};
+
+	body_response(body, format, status, headers)
+}
+
+fn body_response(body: Vec<u8>, format: Format, status: StatusCode, headers: Vec<(&'static str, String)>) -> Response
+{
+	let mut builder = hyper::Response::builder().status(status);
 
-		hyper::Response::builder()
-			.header("Content-Type", "application/json")
-			.header("Access-Control-Allow-Origin", "*")
-			.body(string.into())
-			.unwrap()
+	if let Some(id) = current_request_id() {
+		builder = builder.header(REQUEST_ID_HEADER, id);
+	}
+
+	//a content type of the handler replaces the one of the format
+	let has_content_type = headers
+		.iter()
+		.any(|(name, _)| name.eq_ignore_ascii_case("content-type"));
+
+	for (name, value) in headers {
+		builder = builder.header(name, value);
+	}
+
+	//no body allowed for these
+	let body = if status == StatusCode::NO_CONTENT || status == StatusCode::NOT_MODIFIED {
+		hyper::Body::empty()
+	} else {
+		if !has_content_type {
+			builder = builder.header("Content-Type", format.content_type());
+		}
+
+		body.into()
+	};
+
+	match builder.body(body) {
+		Ok(res) => res,
+		Err(e) => {
+			server_err_owned(
+				500,
+				CoreErrorCodes::ResponseHeader,
+				"Can't create the response".to_string(),
+				Some(format!("wrong header: {}", e)),
+			)
+			.into_response()
+		},
 	}
 }
 
@@ -113,6 +467,8 @@ pub type AppRes<T> = Result<T, ServerCoreError>;
 
 pub type JRes<T> = Result<JsonRes<T>, ServerCoreError>;
 
+pub type JBRes<T> = Result<JsonResBuilder<T>, ServerCoreError>;
+
 pub fn echo<T: Serialize>(obj: T) -> JRes<T>
 {
 	Ok(JsonRes(obj))
@@ -0,0 +1,303 @@
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+use hyper::header::{
+	HeaderName,
+	HeaderValue,
+	CONTENT_SECURITY_POLICY,
+	REFERRER_POLICY,
+	STRICT_TRANSPORT_SECURITY,
+	X_CONTENT_TYPE_OPTIONS,
+	X_FRAME_OPTIONS,
+};
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use uuid::Uuid;
+
+/**
+Is replaced with the nonce of the request in the content security policy, e.g. `script-src 'self' 'nonce-{nonce}'`
+ */
+pub const CSP_NONCE_PLACEHOLDER: &str = "{nonce}";
+
+static SECURITY_HEADERS: OnceLock<Arc<SecurityHeadersConfig>> = OnceLock::new();
+
+tokio::task_local! {
+	static CSP_NONCE: Option<String>;
+}
+
+/**
+Sets the config for the security headers middleware. Without a config the defaults are used.
+ */
+pub fn set_security_headers_config(config: SecurityHeadersConfig)
+{
+	let _ = SECURITY_HEADERS.set(Arc::new(config));
+}
+
+pub fn security_headers_config() -> Arc<SecurityHeadersConfig>
+{
+	SECURITY_HEADERS
+		.get_or_init(|| Arc::new(SecurityHeadersConfig::default()))
+		.clone()
+}
+
+/**
+The csp nonce of the current request, if the csp of the route contains the nonce placeholder
+ */
+pub fn current_csp_nonce() -> Option<String>
+{
+	CSP_NONCE.try_with(|n| n.clone()).ok().flatten()
+}
+
+/**
+Adds the nonce attribute to every script and style tag of the html. Tags in comments and in the content of script and style tags are skipped.
+ */
+pub fn inject_csp_nonce(html: &str, nonce: &str) -> String
+{
+	//ascii lowercase keeps the byte positions
+	let lower = html.to_ascii_lowercase();
+
+	let mut out = String::with_capacity(html.len() + 64);
+	let mut last = 0;
+	let mut i = 0;
+
+	while let Some(pos) = lower[i..].find('<') {
+		let start = i + pos + 1;
+		i = start;
+
+		if lower[start..].starts_with("!--") {
+			match lower[start..].find("-->") {
+				Some(end) => {
+					i = start + end + 3;
+					continue;
+				},
+				None => break,
+			}
+		}
+
+		let tag = if lower[start..].starts_with("script") {
+			"script"
+		} else if lower[start..].starts_with("style") {
+			"style"
+		} else {
+			continue;
+		};
+
+		let end = start + tag.len();
+
+		//not for tags like <scripts>
+		if !lower[end..].starts_with(|c: char| c.is_ascii_whitespace() || c == '>') {
+			continue;
+		}
+
+		out.push_str(&html[last..end]);
+		out.push_str(&format!(" nonce=\"{}\"", nonce));
+		last = end;
+
+		//the content is raw text, e.g. document.write("<script>") is no tag
+		match lower[end..].find(&format!("</{}", tag)) {
+			Some(close) => i = end + close + 2,
+			None => break,
+		}
+	}
+
+	out.push_str(&html[last..]);
+
+	out
+}
+
+/**
+# Security headers config
+
+A header with None is not sent. The defaults:
+
+- `Strict-Transport-Security: max-age=31536000; includeSubDomains`
+- `X-Content-Type-Options: nosniff`
+- `X-Frame-Options: DENY`
+- `Referrer-Policy: strict-origin-when-cross-origin`
+- `Content-Security-Policy: default-src 'self'; object-src 'none'; base-uri 'self'; frame-ancestors 'none'`
+ */
+#[derive(Debug, Clone)]
+pub struct SecurityHeadersConfig
+{
+	pub hsts: Option<String>,
+	pub content_type_options: Option<String>,
+	pub frame_options: Option<String>,
+	pub referrer_policy: Option<String>,
+	pub content_security_policy: Option<String>,
+}
+
+impl Default for SecurityHeadersConfig
+{
+	fn default() -> Self
+	{
+		Self {
+			hsts: Some("max-age=31536000; includeSubDomains".to_string()),
+			content_type_options: Some("nosniff".to_string()),
+			frame_options: Some("DENY".to_string()),
+			referrer_policy: Some("strict-origin-when-cross-origin".to_string()),
+			content_security_policy: Some("default-src 'self'; object-src 'none'; base-uri 'self'; frame-ancestors 'none'".to_string()),
+		}
+	}
+}
+
+impl SecurityHeadersConfig
+{
+	pub fn hsts(mut self, hsts: Option<&str>) -> Self
+	{
+		self.hsts = hsts.map(|h| h.to_string());
+		self
+	}
+
+	pub fn content_type_options(mut self, options: Option<&str>) -> Self
+	{
+		self.content_type_options = options.map(|o| o.to_string());
+		self
+	}
+
+	pub fn frame_options(mut self, options: Option<&str>) -> Self
+	{
+		self.frame_options = options.map(|o| o.to_string());
+		self
+	}
+
+	pub fn referrer_policy(mut self, policy: Option<&str>) -> Self
+	{
+		self.referrer_policy = policy.map(|p| p.to_string());
+		self
+	}
+
+	/**
+	Use the nonce placeholder to create a nonce for every request
+	 */
+	pub fn content_security_policy(mut self, policy: Option<&str>) -> Self
+	{
+		self.content_security_policy = policy.map(|p| p.to_string());
+		self
+	}
+
+	fn uses_nonce(&self) -> bool
+	{
+		self.content_security_policy
+			.as_deref()
+			.is_some_and(|csp| csp.contains(CSP_NONCE_PLACEHOLDER))
+	}
+
+	/**
+	Sets the headers which are not already set by the handler
+	 */
+	pub fn apply(&self, nonce: Option<&str>, res: &mut Response)
+	{
+		let csp = self
+			.content_security_policy
+			.as_ref()
+			.map(|csp| csp.replace(CSP_NONCE_PLACEHOLDER, nonce.unwrap_or_default()));
+
+		let headers: [(HeaderName, Option<&String>); 5] = [
+			(STRICT_TRANSPORT_SECURITY, self.hsts.as_ref()),
+			(X_CONTENT_TYPE_OPTIONS, self.content_type_options.as_ref()),
+			(X_FRAME_OPTIONS, self.frame_options.as_ref()),
+			(REFERRER_POLICY, self.referrer_policy.as_ref()),
+			(CONTENT_SECURITY_POLICY, csp.as_ref()),
+		];
+
+		let res_headers = res.headers_mut();
+
+		for (name, value) in headers {
+			if let Some(value) = value.and_then(|v| HeaderValue::from_str(v).ok()) {
+				res_headers.entry(name).or_insert(value);
+			}
+		}
+	}
+}
+
+//__________________________________________________________________________________________________
+
+/**
+# Middleware for security headers
+
+Adds the security headers of the config to every response. Headers that the handler already set are not changed.
+
+If the content security policy contains the nonce placeholder, a new nonce is created for every request.
+The handler gets it with `current_csp_nonce`, the simple static server adds it to the script and style tags of html files.
+
+Use `security_headers_with` for a route with another config.
+
+````ignore
+set_security_headers_config(
+	SecurityHeadersConfig::default().content_security_policy(Some("default-src 'self'; script-src 'self' 'nonce-{nonce}'")),
+);
+
+router.get("/api/user", r(user_handler).add(security_headers_transform));
+
+//allow the page in an iframe of the same origin
+router.get(
+	"/embed",
+	r(embed_handler).add(security_headers_with(
+		SecurityHeadersConfig::default().frame_options(Some("SAMEORIGIN")),
+	)),
+);
+````
+ */
+pub struct SecurityHeaders<S>
+{
+	inner: S,
+	//None for the global config, it is read for every request like the cors config
+	config: Option<Arc<SecurityHeadersConfig>>,
+}
+
+impl<S> Service<Request> for SecurityHeaders<S>
+where
+	S: Service<Request, Output = Response>,
+{
+	type Output = S::Output;
+
+	fn call(&self, req: Request) -> impl Future<Output = Self::Output> + Send + 'static
+	{
+		let config = self.config.clone().unwrap_or_else(security_headers_config);
+
+		//a uuid v4 has enough random bits for a nonce
+		let nonce = if config.uses_nonce() {
+			Some(Uuid::new_v4().simple().to_string())
+		} else {
+			None
+		};
+
+		let next = CSP_NONCE.scope(nonce.clone(), self.inner.call(req));
+
+		async move {
+			let mut res = next.await;
+
+			config.apply(nonce.as_deref(), &mut res);
+
+			res
+		}
+	}
+}
+
+/**
+The security headers middleware with the config of `set_security_headers_config`.
+
+The config is read for every request, so it can also be set after the routes are built.
+ */
+pub fn security_headers_transform<S>(inner: S) -> SecurityHeaders<S>
+{
+	SecurityHeaders {
+		inner,
+		config: None,
+	}
+}
+
+/**
+The security headers middleware with an own config for a route
+ */
+pub fn security_headers_with<S>(config: SecurityHeadersConfig) -> impl Fn(S) -> SecurityHeaders<S> + Send + Sync + 'static
+{
+	let config = Arc::new(config);
+
+	move |inner| {
+		SecurityHeaders {
+			inner,
+			config: Some(config.clone()),
+		}
+	}
+}
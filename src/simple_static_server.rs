@@ -1,35 +1,48 @@
 use std::ffi::OsStr;
 use std::path::Path;
 
-use hyper::header::{HeaderValue, ACCEPT_ENCODING};
+use hyper::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_LENGTH};
 use rustgram::service::IntoResponse;
 use rustgram::{Request, Response};
 use tokio::sync::OnceCell;
 
 use crate::error::{CoreErrorCodes, ServerCoreError, ServerErrorConstructor};
 use crate::file::{FileHandler, LocalStorage};
+use crate::security_headers::{current_csp_nonce, inject_csp_nonce};
 use crate::url_helper::get_name_param_from_req;
 
 pub static LOCAL_FILE_HANDLER: OnceCell<LocalStorage> = OnceCell::const_new();
 
 pub async fn read_file_from_root(req: Request, cache: Option<&str>) -> Response
 {
-	let path = req.uri().path();
-	let headers = req.headers().get(ACCEPT_ENCODING);
-
-	read_file(headers, path, cache).await
+	read_file_with_headers(req.headers(), req.uri().path(), cache).await
 }
 
 pub async fn read_file_from_route_path(req: Request, path_search: &str, cache: Option<&str>) -> Response
 {
 	let path = get_name_param_from_req(&req, path_search).unwrap_or("");
-	let headers = req.headers().get(ACCEPT_ENCODING);
 
-	read_file(headers, path, cache).await
+	read_file_with_headers(req.headers(), path, cache).await
 }
 
 pub async fn read_file(accept_encoding_header: Option<&HeaderValue>, path: &str, cache: Option<&str>) -> Response
 {
+	let mut headers = HeaderMap::new();
+
+	if let Some(h) = accept_encoding_header {
+		headers.insert(ACCEPT_ENCODING, h.clone());
+	}
+
+	read_file_with_headers(&headers, path, cache).await
+}
+
+/**
+Like read_file but with the Range headers of the request, e.g. for video seeking
+ */
+pub async fn read_file_with_headers(headers: &HeaderMap, path: &str, cache: Option<&str>) -> Response
+{
+	let accept_encoding_header = headers.get(ACCEPT_ENCODING);
+
 	let file = if path.is_empty() || path == "/" { "index.html" } else { path };
 	let mut file = file.to_owned();
 
@@ -61,6 +74,8 @@ pub async fn read_file(accept_encoding_header: Option<&HeaderValue>, path: &str,
 		_ => return ServerCoreError::new_msg(404, CoreErrorCodes::PageNotFound, "Page not found").into_response(),
 	};
 
+	let is_html = ext == "html";
+
 	let encoding = match (ext == "js" || ext == "wasm", accept_encoding_header) {
 		(true, Some(h)) => {
 			if let Ok(h) = std::str::from_utf8(h.as_bytes()) {
@@ -84,7 +99,13 @@ pub async fn read_file(accept_encoding_header: Option<&HeaderValue>, path: &str,
 
 	let handler = LOCAL_FILE_HANDLER.get().unwrap();
 
-	match handler.get_part(&file, Some(content_type)).await {
+	//the nonce is added to the whole html, so no ranges here
+	let range_headers = if is_html { HeaderMap::new() } else { headers.clone() };
+
+	match handler
+		.get_part_with_range(&file, Some(content_type), &range_headers)
+		.await
+	{
 		Ok(mut res) => {
 			let res_headers = res.headers_mut();
 
@@ -99,14 +120,50 @@ pub async fn read_file(accept_encoding_header: Option<&HeaderValue>, path: &str,
 				res_headers.insert("Content-Encoding", HeaderValue::from_static(e));
 			}
 
-			res
+			if is_html {
+				add_csp_nonce(res).await
+			} else {
+				res
+			}
 		},
 		Err(_e) => {
 			//try index
-			handler
-				.get_part("index.html", Some("html"))
-				.await
-				.unwrap_or_else(|e| Into::<ServerCoreError>::into(e).into_response())
+			match handler.get_part("index.html", Some("html")).await {
+				Ok(res) => add_csp_nonce(res).await,
+				Err(e) => Into::<ServerCoreError>::into(e).into_response(),
+			}
 		},
 	}
 }
+
+/**
+Adds the csp nonce of the security headers middleware to the script and style tags of the html
+ */
+async fn add_csp_nonce(res: Response) -> Response
+{
+	let nonce = match current_csp_nonce() {
+		Some(n) => n,
+		None => return res,
+	};
+
+	let (mut parts, body) = res.into_parts();
+
+	let html = match hyper::body::to_bytes(body).await {
+		Ok(html) => html,
+		Err(_e) => return ServerCoreError::new_msg(500, CoreErrorCodes::FileDownload, "Can't read the file").into_response(),
+	};
+
+	//don't change the bytes of html files in another encoding
+	let html = match std::str::from_utf8(&html) {
+		Ok(h) => inject_csp_nonce(h, &nonce),
+		Err(_e) => return Response::from_parts(parts, hyper::Body::from(html)),
+	};
+
+	//the nonce is new for every request
+	parts.headers.remove(CONTENT_LENGTH);
+	parts
+		.headers
+		.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+
+	Response::from_parts(parts, hyper::Body::from(html))
+}
@@ -0,0 +1,231 @@
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{self, Stream};
+use futures::StreamExt;
+use rustgram::service::IntoResponse;
+use rustgram::{Request, Response};
+use serde::Serialize;
+use tokio::time::{interval_at, Instant, Interval};
+
+use crate::input_helper::json_to_string;
+use crate::log;
+use crate::request_id::{current_request_id, REQUEST_ID_HEADER};
+
+pub const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/**
+The id of the last event that the client got before the reconnect. Use it to resume the events.
+ */
+pub fn get_last_event_id(req: &Request) -> Option<&str>
+{
+	req.headers()
+		.get(LAST_EVENT_ID_HEADER)
+		.and_then(|h| h.to_str().ok())
+}
+
+/**
+A single event, the data is sent as json
+ */
+pub struct SseEvent<T: Serialize>
+{
+	pub id: Option<String>,
+	pub event: Option<String>,
+	pub retry: Option<Duration>,
+	pub data: T,
+}
+
+impl<T: Serialize> SseEvent<T>
+{
+	pub fn new(data: T) -> Self
+	{
+		Self {
+			id: None,
+			event: None,
+			retry: None,
+			data,
+		}
+	}
+
+	pub fn id(mut self, id: impl ToString) -> Self
+	{
+		self.id = Some(id.to_string());
+		self
+	}
+
+	/**
+	The name of the event for addEventListener in the browser
+	 */
+	pub fn event(mut self, event: impl Into<String>) -> Self
+	{
+		self.event = Some(event.into());
+		self
+	}
+
+	/**
+	The time the client should wait before it reconnects
+	 */
+	pub fn retry(mut self, retry: Duration) -> Self
+	{
+		self.retry = Some(retry);
+		self
+	}
+
+	fn to_bytes(&self) -> Option<Bytes>
+	{
+		let data = match json_to_string(&self.data) {
+			Ok(d) => d,
+			Err(e) => {
+				log::error(&format!(
+					"sse event not sent: {}",
+					e.msg_owned.as_deref().unwrap_or(e.msg)
+				));
+				return None;
+			},
+		};
+
+		let mut out = String::new();
+
+		//new lines would end the field
+		if let Some(id) = &self.id {
+			out += &format!("id: {}\n", remove_new_lines(id));
+		}
+
+		if let Some(event) = &self.event {
+			out += &format!("event: {}\n", remove_new_lines(event));
+		}
+
+		if let Some(retry) = self.retry {
+			out += &format!("retry: {}\n", retry.as_millis());
+		}
+
+		//json has no new lines
+		out += &format!("data: {}\n\n", data);
+
+		Some(Bytes::from(out))
+	}
+}
+
+fn remove_new_lines(s: &str) -> String
+{
+	s.replace(['\n', '\r'], "")
+}
+
+/**
+# Server-sent events
+
+Sends every event of the stream. The response ends with the stream.
+
+````ignore
+async fn progress(req: Request) -> SseRes<impl Stream<Item = SseEvent<Progress>> + Send + 'static, Progress>
+{
+	let start = get_last_event_id(&req).and_then(|id| id.parse().ok()).unwrap_or(0);
+
+	let events = job_progress_stream(start).map(|p| SseEvent::new(p).id(p.step).event("progress"));
+
+	SseRes::new(events).keep_alive(Duration::from_secs(15))
+}
+````
+ */
+pub struct SseRes<S, T>
+where
+	S: Stream<Item = SseEvent<T>> + Send + 'static,
+	T: Serialize,
+{
+	events: S,
+	keep_alive: Option<Duration>,
+}
+
+impl<S, T> SseRes<S, T>
+where
+	S: Stream<Item = SseEvent<T>> + Send + 'static,
+	T: Serialize,
+{
+	pub fn new(events: S) -> Self
+	{
+		Self {
+			events,
+			keep_alive: None,
+		}
+	}
+
+	/**
+	Sends a comment after every interval, so proxies don't close the connection.
+	A zero interval disables the keep-alive.
+	 */
+	pub fn keep_alive(mut self, interval: Duration) -> Self
+	{
+		//tokio panics for an interval of zero
+		self.keep_alive = if interval.is_zero() { None } else { Some(interval) };
+		self
+	}
+}
+
+type EventStream<T> = Pin<Box<dyn Stream<Item = SseEvent<T>> + Send>>;
+
+async fn next_chunk<T: Serialize>(events: &mut EventStream<T>, keep_alive: &mut Option<Interval>) -> Option<Bytes>
+{
+	loop {
+		let tick = async {
+			match keep_alive {
+				Some(i) => {
+					i.tick().await;
+				},
+				None => std::future::pending().await,
+			}
+		};
+
+		//events first, a keep-alive is only needed if there are no events
+		tokio::select! {
+			biased;
+
+			event = events.next() => {
+				match event?.to_bytes() {
+					Some(b) => {
+						//the event keeps the connection alive too, the next keep-alive only after a full interval
+						if let Some(i) = keep_alive {
+							i.reset();
+						}
+
+						return Some(b);
+					},
+					//skip events which can't be serialized
+					None => continue,
+				}
+			},
+			_ = tick => return Some(Bytes::from_static(b": keep-alive\n\n")),
+		}
+	}
+}
+
+impl<S, T> IntoResponse<Response> for SseRes<S, T>
+where
+	S: Stream<Item = SseEvent<T>> + Send + 'static,
+	T: Serialize + 'static,
+{
+	fn into_response(self) -> Response
+	{
+		let events: EventStream<T> = Box::pin(self.events);
+		let keep_alive = self.keep_alive.map(|d| interval_at(Instant::now() + d, d));
+
+		let body = stream::unfold((events, keep_alive), |(mut events, mut keep_alive)| {
+			async move {
+				let chunk = next_chunk(&mut events, &mut keep_alive).await?;
+
+				Some((Ok::<_, Infallible>(chunk), (events, keep_alive)))
+			}
+		});
+
+		let mut builder = hyper::Response::builder()
+			.header("Content-Type", "text/event-stream")
+			.header("Cache-Control", "no-cache");
+
+		if let Some(id) = current_request_id() {
+			builder = builder.header(REQUEST_ID_HEADER, id);
+		}
+
+		builder.body(hyper::Body::wrap_stream(body)).unwrap()
+	}
+}
@@ -1,18 +1,29 @@
 use std::env;
 use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
 use redis::{FromRedisValue, ToRedisArgs};
+#[cfg(feature = "sqlite")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "sqlite")]
+use serde::Serialize;
 use tokio::sync::OnceCell;
 
-use crate::cache::{ArrayCache, Cache, RedisCache};
+#[cfg(feature = "sqlite")]
+use crate::cache::SqliteCache;
+use crate::cache::{ArrayCache, Cache, Lock, LockGuard, RedisCache, TieredCache, SHORT_TTL, TIERED_CACHE_CHANNEL};
+use crate::log;
 use crate::res::AppRes;
+#[cfg(feature = "sqlite")]
+use crate::static_var::db;
 
 static CACHE: OnceCell<Box<dyn Cache<String>>> = OnceCell::const_new();
+static LOCK: OnceCell<Arc<dyn Lock>> = OnceCell::const_new();
 
 async fn array_cache_init_cache<T: 'static + Clone + Send + Sync>() -> Box<dyn Cache<T>>
 {
-	#[cfg(debug_assertions)]
-	println!("init array cache");
+	log::debug("init array cache");
 
 	Box::new(ArrayCache::new())
 }
@@ -21,12 +32,45 @@ async fn redis_init_cache<T: 'static + Clone + Send + Sync + FromRedisValue + To
 {
 	let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
 
-	#[cfg(debug_assertions)]
-	println!("init redis");
+	log::debug("init redis");
 
 	Box::new(RedisCache::new(&redis_url))
 }
 
+async fn tiered_init_cache<T: 'static + Clone + Send + Sync + FromRedisValue + ToRedisArgs>() -> Box<dyn Cache<T>>
+{
+	let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+	let l1_ttl = env::var("CACHE_L1_TTL").map_or(SHORT_TTL, |t| t.parse().unwrap());
+	let channel = env::var("CACHE_CHANNEL").unwrap_or_else(|_| TIERED_CACHE_CHANNEL.to_string());
+
+	log::debug("init tiered cache");
+
+	Box::new(TieredCache::new(&redis_url, l1_ttl, &channel))
+}
+
+#[cfg(feature = "sqlite")]
+async fn sqlite_init_cache<T: 'static + Clone + Send + Sync + Serialize + DeserializeOwned>() -> Box<dyn Cache<T>>
+{
+	//the cache tables are in the app db
+	db::init_db().await;
+
+	log::debug("init sqlite cache");
+
+	Box::new(SqliteCache::new(db::db()).await.unwrap())
+}
+
+async fn array_init_lock() -> Arc<dyn Lock>
+{
+	Arc::new(ArrayCache::<String>::new())
+}
+
+async fn redis_init_lock() -> Arc<dyn Lock>
+{
+	let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+
+	Arc::new(RedisCache::<String>::new(&redis_url))
+}
+
 pub async fn init_cache()
 {
 	let cache = env::var("CACHE").unwrap_or_else(|_| "1".to_string());
@@ -34,11 +78,28 @@ pub async fn init_cache()
 	match cache.as_str() {
 		"1" => {
 			CACHE.get_or_init(array_cache_init_cache::<String>).await;
+			LOCK.get_or_init(array_init_lock).await;
 		},
 		"2" => {
 			CACHE.get_or_init(redis_init_cache::<String>).await;
+			LOCK.get_or_init(redis_init_lock).await;
+		},
+		"3" => {
+			CACHE.get_or_init(tiered_init_cache::<String>).await;
+			LOCK.get_or_init(redis_init_lock).await;
+		},
+		#[cfg(feature = "sqlite")]
+		"4" => {
+			CACHE.get_or_init(sqlite_init_cache::<String>).await;
+			//single node, so the locks can stay in the process
+			LOCK.get_or_init(array_init_lock).await;
+		},
+		_ => {
+			panic!(
+				"Cache init error: Please choose either `1` for array cache, `2` for redis cache, `3` for array cache in front of redis or `4` for \
+				 sqlite cache (sqlite feature only)."
+			)
 		},
-		_ => panic!("Cache init error: Please choose either `1` for array cache or `2` for redis cache."),
 	}
 }
 
@@ -57,6 +118,30 @@ pub fn add(key: String, value: String, ttl: usize) -> impl Future<Output = AppRe
 	cache.add(key, value, ttl)
 }
 
+#[allow(clippy::needless_lifetimes)]
+pub fn get_and_touch<'a>(key: &'a str, ttl: usize) -> impl Future<Output = AppRes<Option<String>>> + 'a
+{
+	let cache = CACHE.get().unwrap();
+
+	cache.get_and_touch(key, ttl)
+}
+
+#[allow(clippy::needless_lifetimes)]
+pub fn ttl<'a>(key: &'a str) -> impl Future<Output = AppRes<Option<Duration>>> + 'a
+{
+	let cache = CACHE.get().unwrap();
+
+	cache.ttl(key)
+}
+
+#[allow(clippy::needless_lifetimes)]
+pub fn expire<'a>(key: &'a str, ttl: usize) -> impl Future<Output = AppRes<bool>> + 'a
+{
+	let cache = CACHE.get().unwrap();
+
+	cache.expire(key, ttl)
+}
+
 #[allow(clippy::needless_lifetimes)]
 pub fn delete<'a>(key: &'a str) -> impl Future<Output = AppRes<()>> + 'a
 {
@@ -71,3 +156,41 @@ pub fn delete_multiple<'a>(keys: &'a [&str]) -> impl Future<Output = AppRes<()>>
 
 	cache.delete_multiple(keys)
 }
+
+#[allow(clippy::needless_lifetimes)]
+pub fn incr<'a>(key: &'a str, by: i64, ttl: usize) -> impl Future<Output = AppRes<i64>> + 'a
+{
+	let cache = CACHE.get().unwrap();
+
+	cache.incr(key, by, ttl)
+}
+
+#[allow(clippy::needless_lifetimes)]
+pub fn decr<'a>(key: &'a str, by: i64, ttl: usize) -> impl Future<Output = AppRes<i64>> + 'a
+{
+	let cache = CACHE.get().unwrap();
+
+	cache.decr(key, by, ttl)
+}
+
+/**
+The global cache, e.g. for the rate limiter
+ */
+pub fn cache<'a>() -> &'a dyn Cache<String>
+{
+	CACHE.get().unwrap().as_ref()
+}
+
+pub async fn try_lock(key: &str, ttl: Duration) -> AppRes<Option<LockGuard>>
+{
+	let lock = LOCK.get().unwrap();
+
+	LockGuard::try_lock(lock.clone(), key, ttl).await
+}
+
+pub async fn lock_with_wait(key: &str, ttl: Duration, timeout: Duration) -> AppRes<Option<LockGuard>>
+{
+	let lock = LOCK.get().unwrap();
+
+	LockGuard::lock_with_wait(lock.clone(), key, ttl, timeout).await
+}
@@ -0,0 +1,91 @@
+use std::env;
+
+use regex::Regex;
+
+use crate::cors::{set_cors_config, CorsConfig};
+
+fn list_var(name: &str) -> Option<Vec<String>>
+{
+	let var = env::var(name).ok()?;
+
+	Some(
+		var.split(',')
+			.map(|v| v.trim().to_string())
+			.filter(|v| !v.is_empty())
+			.collect(),
+	)
+}
+
+/**
+Sets the cors config from the env. Lists are comma separated, missing vars use the defaults.
+
+- CORS_ALLOWED_ORIGINS: the origins or `*`, default is `*`
+- CORS_ORIGIN_REGEX: a regex for the origins, e.g. for sub domains
+- CORS_ALLOWED_METHODS
+- CORS_ALLOWED_HEADERS
+- CORS_EXPOSE_HEADERS
+- CORS_MAX_AGE: in sec
+- CORS_ALLOW_CREDENTIALS: `1` or `true`, only with own origins and not with `*`
+
+Panics for invalid values.
+ */
+pub fn init_cors()
+{
+	let mut config = CorsConfig::default();
+
+	if let Some(origins) = list_var("CORS_ALLOWED_ORIGINS") {
+		config.origins = origins;
+	}
+
+	if let Ok(regex) = env::var("CORS_ORIGIN_REGEX") {
+		//only the regex origins when no origins are set
+		if env::var("CORS_ALLOWED_ORIGINS").is_err() {
+			config.origins.clear();
+		}
+
+		let regex = Regex::new(&regex).unwrap_or_else(|e| {
+			panic!(
+				"Cors init error: CORS_ORIGIN_REGEX `{}` is not a valid regex: {}",
+				regex, e
+			)
+		});
+
+		config.origin_regex.push(regex);
+	}
+
+	if let Some(methods) = list_var("CORS_ALLOWED_METHODS") {
+		config.methods = methods;
+	}
+
+	if let Some(headers) = list_var("CORS_ALLOWED_HEADERS") {
+		config.headers = headers;
+	}
+
+	if let Some(headers) = list_var("CORS_EXPOSE_HEADERS") {
+		config.expose_headers = headers;
+	}
+
+	if let Ok(max_age) = env::var("CORS_MAX_AGE") {
+		let max_age = max_age.parse().unwrap_or_else(|_| {
+			panic!(
+				"Cors init error: CORS_MAX_AGE `{}` is not a number of seconds",
+				max_age
+			)
+		});
+
+		config.max_age = Some(max_age);
+	}
+
+	if let Ok(credentials) = env::var("CORS_ALLOW_CREDENTIALS") {
+		config.credentials = credentials == "1" || credentials.eq_ignore_ascii_case("true");
+	}
+
+	if config.credentials && config.allows_any_origin() {
+		panic!(
+			"Cors init error: CORS_ALLOW_CREDENTIALS can't be used with the `*` origin. Set CORS_ALLOWED_ORIGINS or CORS_ORIGIN_REGEX \
+			 to the origins which can use the credentials."
+		)
+	}
+
+	set_cors_config(config);
+}
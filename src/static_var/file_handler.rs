@@ -1,5 +1,6 @@
 use std::env;
 
+use hyper::HeaderMap;
 use rustgram::{Request, Response};
 use tokio::sync::OnceCell;
 
@@ -31,6 +32,16 @@ pub async fn get_part(part_id: &str) -> Result<Response, ServerCoreError>
 	handler.get_part(part_id, None).await
 }
 
+/**
+The part with the Range and If-Range headers of the request, e.g. for video seeking or resumable downloads
+ */
+pub async fn get_part_with_range(part_id: &str, headers: &HeaderMap) -> Result<Response, ServerCoreError>
+{
+	let handler = FILE_HANDLER.get().unwrap();
+
+	handler.get_part_with_range(part_id, None, headers).await
+}
+
 pub async fn upload_part(req: Request, part_id: &str, max_chunk_size: usize) -> Result<usize, ServerCoreError>
 {
 	let handler = FILE_HANDLER.get().unwrap();
@@ -1,9 +1,14 @@
 use std::collections::HashMap;
+use std::fmt::Display;
 use std::str::FromStr;
 
 use rustgram::{Request, RouteParams};
+use serde::de::value::{Error as DeError, MapDeserializer};
+use serde::de::{DeserializeOwned, Error as _, IntoDeserializer, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer};
 
-use crate::error::{server_err, CoreErrorCodes};
+use crate::db::id_handling::check_id_format;
+use crate::error::{server_err, server_err_owned, CoreErrorCodes, FieldError, ServerCoreError, ServerErrorConstructor};
 use crate::res::AppRes;
 
 pub fn get_params(req: &Request) -> AppRes<&RouteParams>
@@ -33,6 +38,201 @@ pub fn get_name_param_from_params<'a>(params: &'a RouteParams, name: &str) -> Ap
 	}
 }
 
+/**
+Parses the route param, e.g. a number or a bool.
+ */
+pub fn get_param<T>(req: &Request, name: &str) -> AppRes<T>
+where
+	T: FromStr,
+	T::Err: Display,
+{
+	let param = get_name_param_from_req(req, name)?;
+
+	param.parse().map_err(|e: T::Err| param_err(name, e))
+}
+
+/**
+Checks if the param is an uuid (v4 or v7)
+ */
+pub fn get_id_param<'a>(req: &'a Request, name: &str) -> AppRes<&'a str>
+{
+	let id = get_name_param_from_req(req, name)?;
+
+	check_id_format(id)?;
+
+	Ok(id)
+}
+
+/**
+Parses the number param and checks if it is between min and max (both inclusive)
+ */
+pub fn get_number_param<T>(req: &Request, name: &str, min: T, max: T) -> AppRes<T>
+where
+	T: FromStr + PartialOrd + Display,
+	T::Err: Display,
+{
+	let number: T = get_param(req, name)?;
+
+	if number < min || number > max {
+		return Err(server_err_owned(
+			400,
+			CoreErrorCodes::ParamOutOfRange,
+			format!("Parameter {} must be between {} and {}", name, min, max),
+			None,
+		)
+		.with_param("min", min)
+		.with_param("max", max));
+	}
+
+	Ok(number)
+}
+
+/**
+# Deserialize all route params into a struct
+
+````ignore
+//route: /user/:user_id/post/:page
+#[derive(Deserialize)]
+struct PostParams
+{
+	user_id: String,
+	page: u32,
+}
+
+let params: PostParams = get_params_as(&req)?;
+````
+ */
+pub fn get_params_as<T: DeserializeOwned>(req: &Request) -> AppRes<T>
+{
+	let params = get_params(req)?;
+
+	T::deserialize(RouteParamsDeserializer(params)).map_err(|e| {
+		server_err_owned(
+			400,
+			CoreErrorCodes::ParamParse,
+			format!("Parameters are not valid: {}", e),
+			None,
+		)
+	})
+}
+
+fn param_err(name: &str, e: impl Display) -> ServerCoreError
+{
+	server_err_owned(
+		400,
+		CoreErrorCodes::ParamParse,
+		format!("Parameter {} is not valid", name),
+		None,
+	)
+	.with_errors(vec![FieldError::new(name, "param", e.to_string())])
+}
+
+//__________________________________________________________________________________________________
+//the route params can't be iterated, so only the fields of the struct are looked up
+
+struct RouteParamsDeserializer<'a>(&'a RouteParams);
+
+impl<'de, 'a> Deserializer<'de> for RouteParamsDeserializer<'a>
+{
+	type Error = DeError;
+
+	fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error>
+	{
+		Err(DeError::custom("route params can only be deserialized into a struct"))
+	}
+
+	fn deserialize_struct<V: Visitor<'de>>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error>
+	{
+		let values = fields.iter().filter_map(|field| {
+			self.0
+				.get(field)
+				.map(|value| (*field, ParamValue(field, value.clone())))
+		});
+
+		visitor.visit_map(MapDeserializer::new(values))
+	}
+
+	forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map enum identifier ignored_any
+	}
+}
+
+/**
+A single param value, numbers and bools are parsed from the string
+ */
+struct ParamValue(&'static str, String);
+
+macro_rules! deserialize_parse {
+	($($method:ident => $visit:ident),*) => {
+		$(
+			fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error>
+			{
+				match self.1.parse() {
+					Ok(v) => visitor.$visit(v),
+					Err(e) => Err(DeError::custom(format!("{}: {}", self.0, e))),
+				}
+			}
+		)*
+	};
+}
+
+impl<'de> Deserializer<'de> for ParamValue
+{
+	type Error = DeError;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error>
+	{
+		visitor.visit_string(self.1)
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error>
+	{
+		visitor.visit_some(self)
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, Self::Error>
+	{
+		//unit variants from the string
+		IntoDeserializer::<DeError>::into_deserializer(self.1).deserialize_enum(name, variants, visitor)
+	}
+
+	deserialize_parse!(
+		deserialize_bool => visit_bool,
+		deserialize_i8 => visit_i8,
+		deserialize_i16 => visit_i16,
+		deserialize_i32 => visit_i32,
+		deserialize_i64 => visit_i64,
+		deserialize_i128 => visit_i128,
+		deserialize_u8 => visit_u8,
+		deserialize_u16 => visit_u16,
+		deserialize_u32 => visit_u32,
+		deserialize_u64 => visit_u64,
+		deserialize_u128 => visit_u128,
+		deserialize_f32 => visit_f32,
+		deserialize_f64 => visit_f64
+	);
+
+	forward_to_deserialize_any! {
+		char str string bytes byte_buf unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct identifier ignored_any
+	}
+}
+
+impl<'de> IntoDeserializer<'de, DeError> for ParamValue
+{
+	type Deserializer = Self;
+
+	fn into_deserializer(self) -> Self::Deserializer
+	{
+		self
+	}
+}
+
+/**
+The raw query params without decoding. Use get_query for typed params.
+ */
 pub fn get_query_params(req: &Request) -> AppRes<HashMap<String, String>>
 {
 	let query = match req.uri().query() {
@@ -52,11 +252,74 @@ pub fn get_query_params(req: &Request) -> AppRes<HashMap<String, String>>
 	Ok(params)
 }
 
+/**
+# Typed url query
+
+The query is percent-decoded and nested or array params are supported, e.g. `?ids[0]=a&ids[1]=b&filter[name]=abc`.
+
+Without a query, T is deserialized from the empty query, so optional or default fields are used.
+
+````ignore
+#[derive(Deserialize)]
+struct ListQuery
+{
+	#[serde(default)]
+	page: u32,
+	search: Option<String>,
+}
+
+let query: ListQuery = get_query(&req)?;
+````
+ */
+pub fn get_query<T: DeserializeOwned>(req: &Request) -> AppRes<T>
+{
+	let query = req.uri().query().unwrap_or_default();
+
+	//not strict to also accept percent-encoded brackets
+	let tracked: PathTracked<T> = serde_qs::Config::new(5, false)
+		.deserialize_str(query)
+		.map_err(|e| query_err(None, e.to_string()))?;
+
+	tracked.0.map_err(|(path, msg)| query_err(Some(path), msg))
+}
+
+fn query_err(path: Option<String>, msg: String) -> ServerCoreError
+{
+	//the path is empty (.) for errors of the struct itself, serde only names the field in the msg for missing or unknown fields
+	let field = match path {
+		Some(p) if p != "." => p,
+		_ => {
+			msg.split_once("field `")
+				.and_then(|(_, f)| f.split_once('`'))
+				.map(|(f, _)| f)
+				.unwrap_or("query")
+				.to_string()
+		},
+	};
+
+	ServerCoreError::new_msg(400, CoreErrorCodes::UrlQueryParse, "Url query is not valid").with_errors(vec![FieldError::new(&field, "query", msg)])
+}
+
+/**
+Keeps the path to the failing field, serde_qs doesn't expose its deserializer to wrap it directly
+ */
+struct PathTracked<T>(Result<T, (String, String)>);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for PathTracked<T>
+{
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>
+	{
+		Ok(Self(
+			serde_path_to_error::deserialize(deserializer).map_err(|e| (e.path().to_string(), e.into_inner().to_string())),
+		))
+	}
+}
+
 pub fn get_number_from_url_param<T: FromStr>(number: &str) -> AppRes<T>
 {
 	number
 		.parse()
-		.map_err(|_e| server_err(400, CoreErrorCodes::UnexpectedTime, "It must be a number"))
+		.map_err(|_e| server_err(400, CoreErrorCodes::ParamParse, "It must be a number"))
 }
 
 pub fn get_time_from_url_param(time: &str) -> AppRes<u128>
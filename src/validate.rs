@@ -0,0 +1,251 @@
+use std::sync::OnceLock;
+
+pub use regex::Regex;
+
+use crate::db::id_handling::check_id_format;
+use crate::error::{CoreErrorCodes, FieldError, ServerCoreError, ServerErrorConstructor};
+use crate::res::AppRes;
+
+/**
+# Validation of the user input
+
+Use the Validate derive from the derive_macro feature to implement this.
+
+````ignore
+#[derive(Deserialize, Validate)]
+pub struct RegisterInput
+{
+	#[validate(length(min = 3, max = 20), regex = "^[a-z0-9_]+$")]
+	name: String,
+	#[validate(email)]
+	email: String,
+	#[validate(range(min = 13, max = 150))]
+	age: Option<u32>,
+	#[validate(uuid)]
+	group_id: String,
+}
+````
+
+Optional fields are only validated if they are set.
+ */
+pub trait Validate
+{
+	fn validation_errors(&self) -> Vec<FieldError>;
+
+	/**
+	Returns a 422 error with every field error
+	 */
+	fn validate(&self) -> AppRes<()>
+	{
+		let errors = self.validation_errors();
+
+		if errors.is_empty() {
+			return Ok(());
+		}
+
+		Err(validation_err(errors))
+	}
+}
+
+pub fn validation_err(errors: Vec<FieldError>) -> ServerCoreError
+{
+	ServerCoreError::new_msg(422, CoreErrorCodes::InputValidation, "Input is not valid").with_errors(errors)
+}
+
+//__________________________________________________________________________________________________
+//the value access for the rules, optional values return None to skip the rule
+
+pub trait ValidateLength
+{
+	fn validate_length(&self) -> Option<usize>;
+}
+
+impl ValidateLength for str
+{
+	fn validate_length(&self) -> Option<usize>
+	{
+		Some(self.chars().count())
+	}
+}
+
+impl ValidateLength for String
+{
+	fn validate_length(&self) -> Option<usize>
+	{
+		self.as_str().validate_length()
+	}
+}
+
+impl<T: ValidateLength + ?Sized> ValidateLength for &T
+{
+	fn validate_length(&self) -> Option<usize>
+	{
+		(*self).validate_length()
+	}
+}
+
+impl<T> ValidateLength for Vec<T>
+{
+	fn validate_length(&self) -> Option<usize>
+	{
+		Some(self.len())
+	}
+}
+
+impl<T: ValidateLength> ValidateLength for Option<T>
+{
+	fn validate_length(&self) -> Option<usize>
+	{
+		self.as_ref().and_then(|v| v.validate_length())
+	}
+}
+
+pub trait ValidateRange
+{
+	fn validate_number(&self) -> Option<f64>;
+}
+
+macro_rules! impl_validate_range {
+	($($t:ty),*) => {
+		$(
+			impl ValidateRange for $t
+			{
+				fn validate_number(&self) -> Option<f64>
+				{
+					Some(*self as f64)
+				}
+			}
+		)*
+	};
+}
+
+impl_validate_range!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+impl<T: ValidateRange> ValidateRange for Option<T>
+{
+	fn validate_number(&self) -> Option<f64>
+	{
+		self.as_ref().and_then(|v| v.validate_number())
+	}
+}
+
+pub trait ValidateStr
+{
+	fn validate_str(&self) -> Option<&str>;
+}
+
+impl ValidateStr for str
+{
+	fn validate_str(&self) -> Option<&str>
+	{
+		Some(self)
+	}
+}
+
+impl ValidateStr for String
+{
+	fn validate_str(&self) -> Option<&str>
+	{
+		Some(self.as_str())
+	}
+}
+
+impl<T: ValidateStr + ?Sized> ValidateStr for &T
+{
+	fn validate_str(&self) -> Option<&str>
+	{
+		(*self).validate_str()
+	}
+}
+
+impl<T: ValidateStr> ValidateStr for Option<T>
+{
+	fn validate_str(&self) -> Option<&str>
+	{
+		self.as_ref().and_then(|v| v.validate_str())
+	}
+}
+
+//__________________________________________________________________________________________________
+//the rules, used by the derive
+
+pub fn check_length<T: ValidateLength + ?Sized>(field: &str, value: &T, min: Option<usize>, max: Option<usize>) -> Option<FieldError>
+{
+	let len = value.validate_length()?;
+
+	match (min, max) {
+		(Some(min), _) if len < min => {
+			Some(FieldError::new(
+				field,
+				"length",
+				format!("Must be at least {} long", min),
+			))
+		},
+		(_, Some(max)) if len > max => {
+			Some(FieldError::new(
+				field,
+				"length",
+				format!("Must be at most {} long", max),
+			))
+		},
+		_ => None,
+	}
+}
+
+pub fn check_range<T: ValidateRange + ?Sized>(field: &str, value: &T, min: Option<f64>, max: Option<f64>) -> Option<FieldError>
+{
+	let number = value.validate_number()?;
+
+	match (min, max) {
+		(Some(min), _) if number < min => Some(FieldError::new(field, "range", format!("Must be at least {}", min))),
+		(_, Some(max)) if number > max => Some(FieldError::new(field, "range", format!("Must be at most {}", max))),
+		_ => None,
+	}
+}
+
+pub fn check_regex<T: ValidateStr + ?Sized>(field: &str, value: &T, regex: &'static OnceLock<Regex>, pattern: &str) -> Option<FieldError>
+{
+	let value = value.validate_str()?;
+
+	//the pattern is compiled when the first value is validated, the derive already rejected invalid patterns
+	let regex = regex.get_or_init(|| Regex::new(pattern).expect("the pattern is checked by the derive macro"));
+
+	if regex.is_match(value) {
+		None
+	} else {
+		Some(FieldError::new(field, "regex", "Has a wrong format".to_string()))
+	}
+}
+
+pub fn check_email<T: ValidateStr + ?Sized>(field: &str, value: &T) -> Option<FieldError>
+{
+	let value = value.validate_str()?;
+
+	let valid = match value.split_once('@') {
+		Some((local, domain)) => {
+			!local.is_empty() &&
+				!domain.contains('@') &&
+				!value.chars().any(char::is_whitespace) &&
+				domain.split('.').filter(|part| !part.is_empty()).count() >= 2 &&
+				!domain.starts_with('.') &&
+				!domain.ends_with('.')
+		},
+		None => false,
+	};
+
+	if valid {
+		None
+	} else {
+		Some(FieldError::new(field, "email", "Must be an email".to_string()))
+	}
+}
+
+pub fn check_uuid<T: ValidateStr + ?Sized>(field: &str, value: &T) -> Option<FieldError>
+{
+	let value = value.validate_str()?;
+
+	match check_id_format(value) {
+		Ok(_) => None,
+		Err(e) => Some(FieldError::new(field, "uuid", e.msg.to_string())),
+	}
+}
@@ -6,8 +6,8 @@ mod ser;
 use std::collections::BTreeMap;
 
 use crate::db::custom_types::date_str::{DateStr, DateTimeStr};
-use crate::error::{server_err, server_err_owned, CoreErrorCodes};
-use crate::input_helper::{json_to_string, qs_to_string};
+use crate::error::{server_err, CoreErrorCodes};
+use crate::input_helper::{input_err, json_to_string, qs_to_string};
 use crate::res::AppRes;
 
 #[derive(Debug)]
@@ -86,13 +86,12 @@ impl Value
 
 	pub fn from_json(str: &str) -> AppRes<Self>
 	{
-		serde_json::from_str(str).map_err(|e| server_err_owned(422, CoreErrorCodes::JsonParse, format!("Wrong input: {:?}", e), None))
+		serde_json::from_str(str).map_err(|e| input_err("json", e))
 	}
 
 	pub fn from_qs(str: &str) -> AppRes<Self>
 	{
-		let map: ValueMap =
-			serde_qs::from_str(str).map_err(|e| server_err_owned(422, CoreErrorCodes::JsonParse, format!("Wrong input: {:?}", e), None))?;
+		let map: ValueMap = serde_qs::from_str(str).map_err(|e| input_err("qs", e))?;
 
 		Ok(map.into())
 	}
@@ -0,0 +1,19 @@
+//every test file only uses some of the helpers
+#![allow(dead_code)]
+
+use rustgram::Response;
+
+pub async fn body_string(res: Response) -> String
+{
+	let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+
+	String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+pub async fn body_bytes(res: Response) -> Vec<u8>
+{
+	hyper::body::to_bytes(res.into_body())
+		.await
+		.unwrap()
+		.to_vec()
+}
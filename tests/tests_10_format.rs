@@ -0,0 +1,121 @@
+use rustgram::service::Service;
+use rustgram::Request;
+use rustgram_server_util::error::{server_err, CoreErrorCodes};
+use rustgram_server_util::format::{content_negotiation_transform, Format, CBOR_CONTENT_TYPE, MSGPACK_CONTENT_TYPE};
+use rustgram_server_util::input_helper::get_body;
+use rustgram_server_util::res::{AppRes, Res};
+use serde::{Deserialize, Serialize};
+
+mod common;
+
+use common::body_bytes;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct User
+{
+	name: String,
+	age: u32,
+}
+
+async fn user_handler(_req: Request) -> AppRes<Res<User>>
+{
+	Ok(Res(User {
+		name: "abc".to_string(),
+		age: 20,
+	}))
+}
+
+async fn err_handler(_req: Request) -> AppRes<Res<User>>
+{
+	Err(server_err(400, CoreErrorCodes::NoParameter, "No parameter sent"))
+}
+
+fn accept_req(accept: &str) -> Request
+{
+	hyper::Request::builder()
+		.header("Accept", accept)
+		.body(hyper::Body::empty())
+		.unwrap()
+}
+
+#[tokio::test]
+async fn test_negotiation()
+{
+	let service = content_negotiation_transform(user_handler);
+
+	let res = service
+		.call(accept_req("text/html, application/msgpack"))
+		.await;
+	assert_eq!(res.headers().get("Content-Type").unwrap(), MSGPACK_CONTENT_TYPE);
+
+	let body: serde_json::Value = rmp_serde::from_slice(&body_bytes(res).await).unwrap();
+	assert_eq!(body["status"], true);
+	assert_eq!(body["result"]["name"], "abc");
+
+	let res = service.call(accept_req("*/*")).await;
+	assert_eq!(res.headers().get("Content-Type").unwrap(), "application/json");
+
+	//errors in the same format
+	let service = content_negotiation_transform(err_handler);
+
+	let res = service.call(accept_req(CBOR_CONTENT_TYPE)).await;
+	assert_eq!(res.status(), 400);
+	assert_eq!(res.headers().get("Content-Type").unwrap(), CBOR_CONTENT_TYPE);
+
+	let body: serde_json::Value = ciborium::from_reader(&body_bytes(res).await[..]).unwrap();
+	assert_eq!(body["status"], false);
+	assert_eq!(body["err_code"], 40);
+}
+
+#[tokio::test]
+async fn test_body_format()
+{
+	let user = User {
+		name: "abc".to_string(),
+		age: 20,
+	};
+
+	for format in [Format::Json, Format::MsgPack, Format::Cbor] {
+		let mut req = hyper::Request::builder()
+			.header("Content-Type", format.content_type())
+			.body(hyper::Body::from(format.serialize(&user).unwrap()))
+			.unwrap();
+
+		let out: User = get_body(&mut req).await.unwrap();
+		assert_eq!(out, user);
+	}
+
+	let mut req = hyper::Request::builder()
+		.header("Content-Type", "text/xml")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let err = get_body::<User>(&mut req).await.unwrap_err();
+	assert_eq!(err.http_status_code, 415);
+}
+
+#[test]
+fn test_accept_q_values()
+{
+	assert_eq!(
+		Format::from_accept("application/msgpack, application/cbor"),
+		Format::MsgPack
+	);
+
+	//the higher q wins, not the first
+	assert_eq!(
+		Format::from_accept("application/msgpack;q=0.5, application/cbor;q=0.9"),
+		Format::Cbor
+	);
+	assert_eq!(
+		Format::from_accept("application/cbor; q=0.1, application/json"),
+		Format::Json
+	);
+
+	//q=0 is not acceptable
+	assert_eq!(Format::from_accept("application/msgpack;q=0"), Format::Json);
+	assert_eq!(
+		Format::from_accept("application/msgpack;q=0, application/cbor;q=0.2"),
+		Format::Cbor
+	);
+}
@@ -0,0 +1,236 @@
+use hyper::StatusCode;
+use rustgram::service::{IntoResponse, Service};
+use rustgram::Request;
+use rustgram_server_util::conditional::conditional_get_transform;
+use rustgram_server_util::cookie::{Cookie, SameSite};
+use rustgram_server_util::db::custom_types::date_str::DateTimeStr;
+use rustgram_server_util::res::{JBRes, JsonRes};
+
+#[test]
+fn test_cookie()
+{
+	let cookie = Cookie::new("session", "abc")
+		.http_only()
+		.same_site(SameSite::None)
+		.max_age(3600);
+
+	assert_eq!(
+		cookie.to_string(),
+		"session=abc; Max-Age=3600; Path=/; Secure; HttpOnly; SameSite=None"
+	);
+
+	assert_eq!(Cookie::removal("session").to_string(), "session=; Max-Age=0; Path=/");
+}
+
+#[test]
+fn test_cookie_value_encoding()
+{
+	//no new attribute or header from the value
+	let cookie = Cookie::new("session", "a; Domain=evil.com\r\nX: 1");
+
+	assert_eq!(
+		cookie.to_string(),
+		"session=a%3B%20Domain=evil.com%0D%0AX:%201; Path=/"
+	);
+
+	assert_eq!(
+		Cookie::new("a", "1,2 %\"ü\"").to_string(),
+		"a=1%2C2%20%25%22%C3%BC%22; Path=/"
+	);
+}
+
+#[tokio::test]
+async fn test_cookie_invalid()
+{
+	let cookie = Cookie::new("session=1; Secure", "abc");
+	assert_eq!(cookie.check().unwrap_err().error_code, 80);
+
+	let cookie = Cookie::new("session", "abc").path("/; Domain=evil.com");
+	assert_eq!(cookie.check().unwrap_err().error_code, 80);
+
+	let cookie = Cookie::new("session", "abc").domain("example.com; Secure");
+	assert_eq!(cookie.check().unwrap_err().error_code, 80);
+
+	assert!(Cookie::new("session", "abc")
+		.path("/api")
+		.domain("example.com")
+		.check()
+		.is_ok());
+
+	//no panic and no header with the invalid cookie
+	let res = JsonRes("abc")
+		.cookie(Cookie::new("a;b", "1"))
+		.into_response();
+
+	assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+	assert!(res.headers().get("Set-Cookie").is_none());
+}
+
+#[tokio::test]
+async fn test_json_res_builder()
+{
+	let res = JsonRes("abc")
+		.with_status(StatusCode::CREATED)
+		.header("Location", "/api/user/1")
+		.cookie(Cookie::new("a", "1"))
+		.cookie(Cookie::new("b", "2").secure())
+		.into_response();
+
+	assert_eq!(res.status(), StatusCode::CREATED);
+	assert_eq!(res.headers().get("Location").unwrap(), "/api/user/1");
+	//cors headers are set by the cors middleware
+	assert!(res.headers().get("Access-Control-Allow-Origin").is_none());
+	assert_eq!(res.headers().get_all("Set-Cookie").iter().count(), 2);
+
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	assert_eq!(&body[..], br#"{"status":true,"result":"abc"}"#);
+
+	let res = JsonRes(())
+		.with_status(StatusCode::NO_CONTENT)
+		.into_response();
+
+	assert_eq!(res.status(), StatusCode::NO_CONTENT);
+	assert!(res.headers().get("Content-Type").is_none());
+
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	assert!(body.is_empty());
+
+	//the content type of the handler
+	let res = JsonRes(())
+		.header("Content-Type", "application/vnd.api+json")
+		.into_response();
+	assert_eq!(res.headers().get_all("Content-Type").iter().count(), 1);
+	assert_eq!(res.headers().get("Content-Type").unwrap(), "application/vnd.api+json");
+
+	//wrong header value
+	let res = JsonRes(()).header("Location", "a\nb").into_response();
+	assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+async fn list_handler(_req: Request) -> JBRes<Vec<u32>>
+{
+	Ok(JsonRes(vec![1, 2, 3]).etag())
+}
+
+async fn version_handler(_req: Request) -> JBRes<Vec<u32>>
+{
+	let updated: DateTimeStr = "2024-02-29 10:20:30".parse()?;
+
+	Ok(JsonRes(vec![1, 2, 3]).with_etag(5).last_modified(&updated))
+}
+
+async fn hash_and_last_modified_handler(_req: Request) -> JBRes<Vec<u32>>
+{
+	let updated: DateTimeStr = "2024-02-29 10:20:30".parse()?;
+
+	Ok(JsonRes(vec![1, 2, 3]).etag().last_modified(&updated))
+}
+
+async fn created_handler(_req: Request) -> JBRes<Vec<u32>>
+{
+	Ok(JsonRes(vec![1, 2, 3])
+		.with_status(StatusCode::CREATED)
+		.with_etag(5))
+}
+
+fn conditional_req(header: &str, value: &str) -> Request
+{
+	hyper::Request::builder()
+		.header(header, value)
+		.body(hyper::Body::empty())
+		.unwrap()
+}
+
+#[tokio::test]
+async fn test_etag()
+{
+	let service = conditional_get_transform(list_handler);
+
+	let res = service.call(Request::default()).await;
+	assert_eq!(res.status(), StatusCode::OK);
+
+	let etag = res
+		.headers()
+		.get("ETag")
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.to_string();
+	assert!(etag.starts_with('"'));
+
+	let res = service.call(conditional_req("If-None-Match", &etag)).await;
+	assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+	assert_eq!(res.headers().get("ETag").unwrap(), etag.as_str());
+
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	assert!(body.is_empty());
+
+	let res = service
+		.call(conditional_req("If-None-Match", "\"other\""))
+		.await;
+	assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_version_and_last_modified()
+{
+	let service = conditional_get_transform(version_handler);
+
+	let res = service.call(Request::default()).await;
+	assert_eq!(res.headers().get("ETag").unwrap(), "\"5\"");
+	assert_eq!(
+		res.headers().get("Last-Modified").unwrap(),
+		"Thu, 29 Feb 2024 10:20:30 GMT"
+	);
+
+	let res = service
+		.call(conditional_req("If-None-Match", "W/\"5\""))
+		.await;
+	assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+	let res = service
+		.call(conditional_req("If-Modified-Since", "Thu, 29 Feb 2024 10:20:30 GMT"))
+		.await;
+	assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+	let res = service
+		.call(conditional_req("If-Modified-Since", "Thu, 29 Feb 2024 10:20:29 GMT"))
+		.await;
+	assert_eq!(res.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_hash_etag_and_last_modified()
+{
+	let service = conditional_get_transform(hash_and_last_modified_handler);
+
+	let res = service.call(Request::default()).await;
+	let etag = res.headers().get("ETag").unwrap().clone();
+
+	//the 304 from If-Modified-Since must have the same etag as the 200
+	let res = service
+		.call(conditional_req("If-Modified-Since", "Thu, 29 Feb 2024 10:20:30 GMT"))
+		.await;
+	assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+	assert_eq!(res.headers().get("ETag").unwrap(), etag);
+	assert_eq!(
+		res.headers().get("Last-Modified").unwrap(),
+		"Thu, 29 Feb 2024 10:20:30 GMT"
+	);
+}
+
+#[tokio::test]
+async fn test_conditional_only_for_ok()
+{
+	let service = conditional_get_transform(created_handler);
+
+	//a matching etag must not turn the 201 into a 304
+	let res = service
+		.call(conditional_req("If-None-Match", "\"5\""))
+		.await;
+	assert_eq!(res.status(), StatusCode::CREATED);
+	assert_eq!(res.headers().get("ETag").unwrap(), "\"5\"");
+
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	assert!(!body.is_empty());
+}
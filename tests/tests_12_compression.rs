@@ -0,0 +1,114 @@
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder};
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use rustgram_server_util::compression::{compression_transform, parse_accept_encoding, Encoding};
+use rustgram_server_util::res::{echo, JBRes, JRes, JsonRes};
+use tokio::io::AsyncReadExt;
+
+mod common;
+
+use common::body_bytes;
+
+async fn list_handler(_req: Request) -> JRes<Vec<String>>
+{
+	echo(vec!["item".to_string(); 1000])
+}
+
+async fn etag_handler(_req: Request) -> JBRes<Vec<String>>
+{
+	Ok(JsonRes(vec!["item".to_string(); 1000]).with_etag(5))
+}
+
+async fn small_handler(_req: Request) -> JRes<&'static str>
+{
+	echo("abc")
+}
+
+async fn image_handler(_req: Request) -> Response
+{
+	hyper::Response::builder()
+		.header("Content-Type", "image/png")
+		.body(hyper::Body::from(vec![0; 10_000]))
+		.unwrap()
+}
+
+fn encoding_req(accept_encoding: &str) -> Request
+{
+	hyper::Request::builder()
+		.header("Accept-Encoding", accept_encoding)
+		.body(hyper::Body::empty())
+		.unwrap()
+}
+
+#[test]
+fn test_parse_accept_encoding()
+{
+	assert_eq!(parse_accept_encoding("gzip, deflate, br"), Some(Encoding::Br));
+	assert_eq!(parse_accept_encoding("gzip, br;q=0"), Some(Encoding::Gzip));
+	assert_eq!(parse_accept_encoding("deflate, gzip;q=0.5"), Some(Encoding::Deflate));
+	assert_eq!(parse_accept_encoding("identity"), None);
+}
+
+#[tokio::test]
+async fn test_compression()
+{
+	let service = compression_transform(list_handler);
+
+	let res = service.call(encoding_req("gzip, br;q=0")).await;
+	assert_eq!(res.headers().get("Content-Encoding").unwrap(), "gzip");
+	assert_eq!(res.headers().get("Vary").unwrap(), "Accept-Encoding");
+
+	let body = body_bytes(res).await;
+	let mut out = String::new();
+	GzipDecoder::new(&body[..])
+		.read_to_string(&mut out)
+		.await
+		.unwrap();
+	assert!(out.starts_with(r#"{"status":true,"result":["item","#));
+
+	let res = service.call(encoding_req("br")).await;
+	assert_eq!(res.headers().get("Content-Encoding").unwrap(), "br");
+
+	let body = body_bytes(res).await;
+	let mut out = String::new();
+	BrotliDecoder::new(&body[..])
+		.read_to_string(&mut out)
+		.await
+		.unwrap();
+	assert!(out.ends_with(r#""item"]}"#));
+
+	//no accept encoding
+	let res = service.call(Request::default()).await;
+	assert!(res.headers().get("Content-Encoding").is_none());
+	assert_eq!(res.headers().get("Vary").unwrap(), "Accept-Encoding");
+}
+
+#[tokio::test]
+async fn test_compression_weak_etag()
+{
+	let service = compression_transform(etag_handler);
+
+	let res = service.call(encoding_req("gzip")).await;
+	assert_eq!(res.headers().get("Content-Encoding").unwrap(), "gzip");
+	assert_eq!(res.headers().get("ETag").unwrap(), "W/\"5\"");
+
+	//not compressed keeps the strong etag
+	let res = service.call(Request::default()).await;
+	assert_eq!(res.headers().get("ETag").unwrap(), "\"5\"");
+}
+
+#[tokio::test]
+async fn test_compression_skip()
+{
+	let res = compression_transform(small_handler)
+		.call(encoding_req("gzip"))
+		.await;
+	assert!(res.headers().get("Content-Encoding").is_none());
+	assert_eq!(body_bytes(res).await, br#"{"status":true,"result":"abc"}"#);
+
+	let res = compression_transform(image_handler)
+		.call(encoding_req("gzip"))
+		.await;
+	assert!(res.headers().get("Content-Encoding").is_none());
+	assert!(res.headers().get("Vary").is_none());
+}
@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use rustgram::service::IntoResponse;
+use rustgram::Request;
+use rustgram_server_util::sse::{get_last_event_id, SseEvent, SseRes};
+use serde::Serialize;
+
+mod common;
+
+use common::body_string;
+
+#[derive(Serialize)]
+struct Progress
+{
+	step: u32,
+}
+
+#[tokio::test]
+async fn test_sse()
+{
+	let events = futures::stream::iter(1..=2).map(|step| {
+		SseEvent::new(Progress {
+			step,
+		})
+		.id(step)
+		.event("progress\nwrong")
+	});
+
+	let res = SseRes::new(events).into_response();
+
+	assert_eq!(res.headers().get("Content-Type").unwrap(), "text/event-stream");
+	assert_eq!(res.headers().get("Cache-Control").unwrap(), "no-cache");
+
+	assert_eq!(
+		body_string(res).await,
+		"id: 1\nevent: progresswrong\ndata: {\"step\":1}\n\nid: 2\nevent: progresswrong\ndata: {\"step\":2}\n\n"
+	);
+}
+
+#[tokio::test]
+async fn test_sse_keep_alive()
+{
+	let events = futures::stream::iter(1..=1).then(|step| {
+		async move {
+			tokio::time::sleep(Duration::from_millis(100)).await;
+
+			SseEvent::new(Progress {
+				step,
+			})
+			.retry(Duration::from_secs(3))
+		}
+	});
+
+	let res = SseRes::new(events)
+		.keep_alive(Duration::from_millis(20))
+		.into_response();
+
+	let body = body_string(res).await;
+
+	assert!(body.starts_with(": keep-alive\n\n"));
+	assert!(body.ends_with("retry: 3000\ndata: {\"step\":1}\n\n"));
+}
+
+#[tokio::test]
+async fn test_sse_keep_alive_zero()
+{
+	let events = futures::stream::iter(1..=1).map(|step| {
+		SseEvent::new(Progress {
+			step,
+		})
+	});
+
+	let res = SseRes::new(events)
+		.keep_alive(Duration::ZERO)
+		.into_response();
+
+	assert_eq!(body_string(res).await, "data: {\"step\":1}\n\n");
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_sse_keep_alive_reset()
+{
+	//an event every 30ms, so the keep-alive of 50ms is never needed
+	let events = futures::stream::iter(1..=4).then(|step| {
+		async move {
+			tokio::time::sleep(Duration::from_millis(30)).await;
+
+			SseEvent::new(Progress {
+				step,
+			})
+		}
+	});
+
+	let res = SseRes::new(events)
+		.keep_alive(Duration::from_millis(50))
+		.into_response();
+
+	let body = body_string(res).await;
+
+	assert!(!body.contains("keep-alive"));
+	assert!(body.ends_with("data: {\"step\":4}\n\n"));
+}
+
+#[test]
+fn test_last_event_id()
+{
+	let req: Request = hyper::Request::builder()
+		.header("Last-Event-ID", "5")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	assert_eq!(get_last_event_id(&req), Some("5"));
+}
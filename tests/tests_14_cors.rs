@@ -0,0 +1,139 @@
+use hyper::StatusCode;
+use regex::Regex;
+use rustgram::service::Service;
+use rustgram::Request;
+use rustgram_server_util::cors::{cors_transform, set_cors_config, CorsConfig};
+use rustgram_server_util::error::{server_err, CoreErrorCodes};
+use rustgram_server_util::res::{echo, AppRes, JRes};
+
+async fn ok_handler(_req: Request) -> JRes<&'static str>
+{
+	echo("ok")
+}
+
+async fn err_handler(_req: Request) -> AppRes<&'static str>
+{
+	Err(server_err(400, CoreErrorCodes::NoParameter, "No parameter sent"))
+}
+
+fn init()
+{
+	set_cors_config(
+		CorsConfig::new()
+			.allow_origin("https://example.com")
+			.allow_origin_regex(Regex::new(r"^https://[a-z0-9-]+\.example\.com$").unwrap())
+			.allow_credentials(true)
+			.expose_header("X-Request-Id"),
+	);
+}
+
+fn origin_req(method: &str, origin: &str) -> Request
+{
+	hyper::Request::builder()
+		.method(method)
+		.header("Origin", origin)
+		.header("Access-Control-Request-Method", "POST")
+		.body(hyper::Body::empty())
+		.unwrap()
+}
+
+#[tokio::test]
+async fn test_cors_preflight()
+{
+	init();
+
+	let service = cors_transform(err_handler);
+
+	let res = service
+		.call(origin_req("OPTIONS", "https://app.example.com"))
+		.await;
+	assert_eq!(res.status(), StatusCode::NO_CONTENT);
+
+	let headers = res.headers();
+	assert_eq!(
+		headers.get("Access-Control-Allow-Origin").unwrap(),
+		"https://app.example.com"
+	);
+	assert_eq!(headers.get("Access-Control-Allow-Credentials").unwrap(), "true");
+	assert_eq!(headers.get("Access-Control-Max-Age").unwrap(), "86400");
+	assert!(headers
+		.get("Access-Control-Allow-Methods")
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.contains("POST"));
+
+	//not allowed origin
+	let res = service
+		.call(origin_req("OPTIONS", "https://evil.com"))
+		.await;
+	assert_eq!(res.status(), StatusCode::NO_CONTENT);
+	assert!(res.headers().get("Access-Control-Allow-Origin").is_none());
+}
+
+#[tokio::test]
+async fn test_cors_response()
+{
+	init();
+
+	let res = cors_transform(ok_handler)
+		.call(origin_req("GET", "https://example.com"))
+		.await;
+
+	let headers = res.headers();
+	assert_eq!(
+		headers.get("Access-Control-Allow-Origin").unwrap(),
+		"https://example.com"
+	);
+	assert_eq!(headers.get("Access-Control-Expose-Headers").unwrap(), "X-Request-Id");
+	assert_eq!(headers.get("Vary").unwrap(), "Origin");
+
+	//error responses too
+	let res = cors_transform(err_handler)
+		.call(origin_req("POST", "https://example.com"))
+		.await;
+	assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+	assert_eq!(
+		res.headers().get("Access-Control-Allow-Origin").unwrap(),
+		"https://example.com"
+	);
+
+	let res = cors_transform(ok_handler)
+		.call(origin_req("GET", "https://example.com.evil.com"))
+		.await;
+	assert!(res.headers().get("Access-Control-Allow-Origin").is_none());
+	assert!(res
+		.headers()
+		.get("Access-Control-Allow-Credentials")
+		.is_none());
+}
+
+#[test]
+fn test_cors_default_config()
+{
+	let config = CorsConfig::default();
+
+	let res = config.preflight_response(&origin_req("OPTIONS", "https://example.com"));
+	assert_eq!(res.headers().get("Access-Control-Allow-Origin").unwrap(), "*");
+	assert!(res
+		.headers()
+		.get("Access-Control-Allow-Credentials")
+		.is_none());
+
+	//the origin is never reflected for the wildcard, even if the credentials are set without set_cors_config
+	let config = CorsConfig::default().allow_credentials(true);
+
+	let res = config.preflight_response(&origin_req("OPTIONS", "https://example.com"));
+	assert_eq!(res.headers().get("Access-Control-Allow-Origin").unwrap(), "*");
+	assert!(res
+		.headers()
+		.get("Access-Control-Allow-Credentials")
+		.is_none());
+}
+
+#[test]
+#[should_panic(expected = "credentials are not allowed with the `*` origin")]
+fn test_cors_wildcard_with_credentials()
+{
+	set_cors_config(CorsConfig::default().allow_credentials(true));
+}
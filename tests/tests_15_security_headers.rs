@@ -0,0 +1,185 @@
+use rustgram::service::Service;
+use rustgram::{Request, Response};
+use rustgram_server_util::file::LocalStorage;
+use rustgram_server_util::res::{echo, JRes};
+use rustgram_server_util::security_headers::{
+	current_csp_nonce,
+	inject_csp_nonce,
+	security_headers_transform,
+	security_headers_with,
+	SecurityHeadersConfig,
+};
+use rustgram_server_util::simple_static_server::{read_file, LOCAL_FILE_HANDLER};
+
+mod common;
+
+use common::body_string;
+
+async fn ok_handler(_req: Request) -> JRes<&'static str>
+{
+	echo("ok")
+}
+
+async fn frame_handler(_req: Request) -> Response
+{
+	hyper::Response::builder()
+		.header("X-Frame-Options", "SAMEORIGIN")
+		.body(hyper::Body::empty())
+		.unwrap()
+}
+
+async fn nonce_handler(_req: Request) -> JRes<String>
+{
+	echo(current_csp_nonce().unwrap())
+}
+
+async fn page_handler(_req: Request) -> Response
+{
+	read_file(None, "page.html", Some("3600")).await
+}
+
+async fn latin1_handler(_req: Request) -> Response
+{
+	read_file(None, "latin1.html", Some("3600")).await
+}
+
+fn nonce_config() -> SecurityHeadersConfig
+{
+	SecurityHeadersConfig::default().content_security_policy(Some("default-src 'self'; script-src 'self' 'nonce-{nonce}'"))
+}
+
+#[tokio::test]
+async fn test_security_headers()
+{
+	let res = security_headers_transform(ok_handler)
+		.call(Request::default())
+		.await;
+
+	let headers = res.headers();
+	assert_eq!(
+		headers.get("Strict-Transport-Security").unwrap(),
+		"max-age=31536000; includeSubDomains"
+	);
+	assert_eq!(headers.get("X-Content-Type-Options").unwrap(), "nosniff");
+	assert_eq!(headers.get("X-Frame-Options").unwrap(), "DENY");
+	assert_eq!(
+		headers.get("Referrer-Policy").unwrap(),
+		"strict-origin-when-cross-origin"
+	);
+	assert!(headers.get("Content-Security-Policy").is_some());
+
+	//the handler header is not changed
+	let res = security_headers_transform(frame_handler)
+		.call(Request::default())
+		.await;
+	assert_eq!(res.headers().get("X-Frame-Options").unwrap(), "SAMEORIGIN");
+
+	//route config
+	let service = security_headers_with(SecurityHeadersConfig::default().hsts(None))(ok_handler);
+
+	let res = service.call(Request::default()).await;
+	assert!(res.headers().get("Strict-Transport-Security").is_none());
+	assert_eq!(res.headers().get("X-Frame-Options").unwrap(), "DENY");
+}
+
+#[tokio::test]
+async fn test_csp_nonce()
+{
+	let service = security_headers_with(nonce_config())(nonce_handler);
+
+	let res = service.call(Request::default()).await;
+	let csp = res
+		.headers()
+		.get("Content-Security-Policy")
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.to_string();
+
+	let body = body_string(res).await;
+	let nonce = body
+		.trim_start_matches(r#"{"status":true,"result":""#)
+		.trim_end_matches(r#""}"#);
+
+	assert_eq!(
+		csp,
+		format!("default-src 'self'; script-src 'self' 'nonce-{}'", nonce)
+	);
+
+	//a new nonce for every request
+	let res = service.call(Request::default()).await;
+	assert_ne!(
+		res.headers()
+			.get("Content-Security-Policy")
+			.unwrap()
+			.to_str()
+			.unwrap(),
+		csp
+	);
+}
+
+#[test]
+fn test_inject_csp_nonce()
+{
+	let html = r#"<html><head><SCRIPT src="/main.js"></SCRIPT><style>a{}</style><scripts></scripts></head></html>"#;
+
+	assert_eq!(
+		inject_csp_nonce(html, "abc"),
+		r#"<html><head><SCRIPT nonce="abc" src="/main.js"></SCRIPT><style nonce="abc">a{}</style><scripts></scripts></head></html>"#
+	);
+
+	//no nonce for tags in comments or in the script content
+	let html = r#"<!-- <script>old()</script> --><script>document.write("<script>x()</script>")</script><style>a{}</style>"#;
+
+	assert_eq!(
+		inject_csp_nonce(html, "abc"),
+		r#"<!-- <script>old()</script> --><script nonce="abc">document.write("<script>x()</script>")</script><style nonce="abc">a{}</style>"#
+	);
+}
+
+#[tokio::test]
+async fn test_static_server_nonce()
+{
+	let dir = std::env::temp_dir().join("rustgram_security_headers_test");
+	tokio::fs::create_dir_all(&dir).await.unwrap();
+	tokio::fs::write(dir.join("page.html"), "<html><script>run()</script></html>")
+		.await
+		.unwrap();
+	//ä in latin-1
+	tokio::fs::write(dir.join("latin1.html"), b"<html><script>\xe4</script></html>")
+		.await
+		.unwrap();
+
+	LOCAL_FILE_HANDLER
+		.get_or_init(|| async { LocalStorage::new(dir.to_str().unwrap().to_string()) })
+		.await;
+
+	let res = security_headers_with(nonce_config())(page_handler)
+		.call(Request::default())
+		.await;
+
+	assert_eq!(res.headers().get("Cache-Control").unwrap(), "no-store");
+
+	let csp = res
+		.headers()
+		.get("Content-Security-Policy")
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.to_string();
+
+	let body = body_string(res).await;
+	let nonce = body
+		.trim_start_matches(r#"<html><script nonce=""#)
+		.trim_end_matches(r#"">run()</script></html>"#);
+
+	assert!(csp.ends_with(&format!("'nonce-{}'", nonce)));
+
+	//not utf-8, the bytes are sent unchanged
+	let res = security_headers_with(nonce_config())(latin1_handler)
+		.call(Request::default())
+		.await;
+
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	assert_eq!(&body[..], b"<html><script>\xe4</script></html>");
+}
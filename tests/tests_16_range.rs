@@ -0,0 +1,225 @@
+use hyper::{HeaderMap, StatusCode};
+use rustgram_server_util::file::{parse_range, FileHandler, LocalStorage, RangeResult};
+
+mod common;
+
+use common::body_string;
+
+//a file for each test, the tests run in parallel
+async fn storage(file: &str) -> LocalStorage
+{
+	let dir = std::env::temp_dir().join("rustgram_range_test");
+	tokio::fs::create_dir_all(&dir).await.unwrap();
+	tokio::fs::write(dir.join(file), "0123456789")
+		.await
+		.unwrap();
+
+	LocalStorage::new(dir.to_str().unwrap().to_string())
+}
+
+fn range_headers(range: &str) -> HeaderMap
+{
+	let mut headers = HeaderMap::new();
+	headers.insert("Range", range.parse().unwrap());
+
+	headers
+}
+
+#[test]
+fn test_parse_range()
+{
+	assert_eq!(parse_range("bytes=0-4", 10), RangeResult::Partial(vec![(0, 4)]));
+	assert_eq!(parse_range("bytes=5-", 10), RangeResult::Partial(vec![(5, 9)]));
+	assert_eq!(parse_range("bytes=-3", 10), RangeResult::Partial(vec![(7, 9)]));
+	assert_eq!(parse_range("bytes=8-100", 10), RangeResult::Partial(vec![(8, 9)]));
+	assert_eq!(
+		parse_range("bytes=0-1, 20-30, 4-5", 10),
+		RangeResult::Partial(vec![(0, 1), (4, 5)])
+	);
+
+	assert_eq!(parse_range("bytes=10-20", 10), RangeResult::Unsatisfiable);
+	assert_eq!(parse_range("bytes=-0", 10), RangeResult::Unsatisfiable);
+
+	//overlapping and adjacent ranges are merged
+	assert_eq!(parse_range("bytes=0-4,3-6", 10), RangeResult::Partial(vec![(0, 6)]));
+	assert_eq!(
+		parse_range("bytes=4-5,0-1,2-3", 10),
+		RangeResult::Partial(vec![(0, 5)])
+	);
+	assert_eq!(
+		parse_range("bytes=6-7,0-1", 10),
+		RangeResult::Partial(vec![(0, 1), (6, 7)])
+	);
+
+	//more bytes than the file
+	assert_eq!(parse_range("bytes=0-,0-", 10), RangeResult::Full);
+	assert_eq!(parse_range("bytes=0-5,-5", 10), RangeResult::Full);
+
+	//invalid headers are ignored
+	assert_eq!(parse_range("bytes=5-2", 10), RangeResult::Full);
+	assert_eq!(parse_range("items=0-4", 10), RangeResult::Full);
+	assert_eq!(parse_range("bytes=a-4", 10), RangeResult::Full);
+}
+
+#[tokio::test]
+async fn test_range_response()
+{
+	let storage = storage("range.txt").await;
+
+	//full file
+	let res = storage
+		.get_part("range.txt", Some("text/plain"))
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::OK);
+	assert_eq!(res.headers().get("Accept-Ranges").unwrap(), "bytes");
+	assert_eq!(res.headers().get("Content-Length").unwrap(), "10");
+	assert_eq!(body_string(res).await, "0123456789");
+
+	//single range
+	let res = storage
+		.get_part_with_range("range.txt", Some("text/plain"), &range_headers("bytes=2-5"))
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+	assert_eq!(res.headers().get("Content-Range").unwrap(), "bytes 2-5/10");
+	assert_eq!(res.headers().get("Content-Length").unwrap(), "4");
+	assert_eq!(body_string(res).await, "2345");
+
+	//not satisfiable
+	let res = storage
+		.get_part_with_range("range.txt", Some("text/plain"), &range_headers("bytes=20-"))
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+	assert_eq!(res.headers().get("Content-Range").unwrap(), "bytes */10");
+}
+
+#[tokio::test]
+async fn test_multi_range_response()
+{
+	let storage = storage("multi_range.txt").await;
+
+	let res = storage
+		.get_part_with_range("multi_range.txt", Some("text/plain"), &range_headers("bytes=0-1,-2"))
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+
+	let content_type = res
+		.headers()
+		.get("Content-Type")
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.to_string();
+	let boundary = content_type
+		.strip_prefix("multipart/byteranges; boundary=")
+		.unwrap();
+
+	let len: usize = res
+		.headers()
+		.get("Content-Length")
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.parse()
+		.unwrap();
+
+	let body = body_string(res).await;
+	assert_eq!(body.len(), len);
+	assert_eq!(
+		body,
+		format!(
+			"\r\n--{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n--{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 8-9/10\r\n\r\n89\r\n--{b}--\r\n",
+			b = boundary
+		)
+	);
+}
+
+#[tokio::test]
+async fn test_multi_range_large()
+{
+	//larger than the read buffer, so a range is read in more chunks
+	let data: String = (0..200_000)
+		.map(|i| char::from(b'a' + (i % 26) as u8))
+		.collect();
+
+	let dir = std::env::temp_dir().join("rustgram_range_test");
+	tokio::fs::create_dir_all(&dir).await.unwrap();
+	tokio::fs::write(dir.join("multi_range_large.txt"), &data)
+		.await
+		.unwrap();
+
+	let storage = LocalStorage::new(dir.to_str().unwrap().to_string());
+
+	let res = storage
+		.get_part_with_range(
+			"multi_range_large.txt",
+			Some("text/plain"),
+			&range_headers("bytes=150000-150009,10-99999"),
+		)
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+
+	let content_type = res
+		.headers()
+		.get("Content-Type")
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.to_string();
+	let boundary = content_type
+		.strip_prefix("multipart/byteranges; boundary=")
+		.unwrap();
+
+	let body = body_string(res).await;
+
+	//sorted by the start
+	assert_eq!(
+		body,
+		format!(
+			"\r\n--{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 10-99999/200000\r\n\r\n{}\r\n--{b}\r\nContent-Type: \
+			 text/plain\r\nContent-Range: bytes 150000-150009/200000\r\n\r\n{}\r\n--{b}--\r\n",
+			&data[10..100_000],
+			&data[150_000..150_010],
+			b = boundary
+		)
+	);
+}
+
+#[tokio::test]
+async fn test_if_range()
+{
+	let storage = storage("if_range.txt").await;
+
+	let res = storage.get_part("if_range.txt", None).await.unwrap();
+	let last_modified = res.headers().get("Last-Modified").unwrap().clone();
+
+	let mut headers = range_headers("bytes=0-0");
+	headers.insert("If-Range", last_modified);
+
+	let res = storage
+		.get_part_with_range("if_range.txt", None, &headers)
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+
+	//changed file or an etag sends the whole file
+	headers.insert("If-Range", "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+
+	let res = storage
+		.get_part_with_range("if_range.txt", None, &headers)
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::OK);
+
+	headers.insert("If-Range", "\"abc\"".parse().unwrap());
+
+	let res = storage
+		.get_part_with_range("if_range.txt", None, &headers)
+		.await
+		.unwrap();
+	assert_eq!(res.status(), StatusCode::OK);
+}
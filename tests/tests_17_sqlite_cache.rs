@@ -0,0 +1,107 @@
+#![cfg(feature = "sqlite")]
+
+use std::time::Duration;
+
+use rustgram_server_util::cache::{Cache, SqliteCache};
+use rustgram_server_util::db::Db;
+
+fn db_path(name: &str) -> String
+{
+	let dir = std::env::temp_dir().join("rustgram_sqlite_cache_test");
+	std::fs::create_dir_all(&dir).unwrap();
+
+	let path = dir.join(name);
+	let _ = std::fs::remove_file(&path);
+
+	path.to_str().unwrap().to_string()
+}
+
+//the cache needs the db for the whole app lifetime
+fn db(name: &str) -> &'static Db
+{
+	Box::leak(Box::new(Db::new(&db_path(name))))
+}
+
+#[tokio::test]
+async fn test_sqlite_cache()
+{
+	let cache = SqliteCache::<String>::new(db("cache.db")).await.unwrap();
+
+	cache
+		.add("key".to_string(), "value".to_string(), 200)
+		.await
+		.unwrap();
+	assert_eq!(cache.get("key").await.unwrap(), Some("value".to_string()));
+	assert!(cache.ttl("key").await.unwrap().unwrap() <= Duration::from_secs(200));
+
+	//ttl
+	cache
+		.add("key_ttl".to_string(), "value".to_string(), 1)
+		.await
+		.unwrap();
+	tokio::time::sleep(Duration::from_secs(2)).await;
+	assert_eq!(cache.get("key_ttl").await.unwrap(), None);
+	assert_eq!(cache.ttl("key_ttl").await.unwrap(), None);
+
+	//delete
+	cache.delete("key").await.unwrap();
+	assert_eq!(cache.get("key").await.unwrap(), None);
+
+	cache
+		.add("key_1".to_string(), "value".to_string(), 200)
+		.await
+		.unwrap();
+	cache
+		.add("key_2".to_string(), "value".to_string(), 200)
+		.await
+		.unwrap();
+	cache.delete_multiple(&["key_1", "key_2"]).await.unwrap();
+	assert_eq!(cache.get("key_1").await.unwrap(), None);
+	assert_eq!(cache.get("key_2").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_sqlite_cache_counter()
+{
+	let cache = SqliteCache::<String>::new(db("counter.db")).await.unwrap();
+
+	assert_eq!(cache.incr("counter", 1, 200).await.unwrap(), 1);
+	assert_eq!(cache.incr("counter", 2, 200).await.unwrap(), 3);
+	assert_eq!(cache.decr("counter", 1, 200).await.unwrap(), 2);
+
+	//an expired counter starts again
+	assert_eq!(cache.incr("counter_ttl", 5, 1).await.unwrap(), 5);
+	tokio::time::sleep(Duration::from_secs(2)).await;
+	assert_eq!(cache.incr("counter_ttl", 1, 1).await.unwrap(), 1);
+
+	cache.delete("counter").await.unwrap();
+	assert_eq!(cache.incr("counter", 1, 200).await.unwrap(), 1);
+}
+
+#[cfg(feature = "static_var")]
+#[tokio::test]
+async fn test_sqlite_cache_init()
+{
+	use rustgram_server_util::cache;
+
+	std::env::set_var("CACHE", "4");
+	std::env::set_var("DB_PATH", db_path("init.db"));
+
+	cache::init_cache().await;
+
+	cache::add("key".to_string(), "value".to_string(), 200)
+		.await
+		.unwrap();
+	assert_eq!(cache::get("key").await.unwrap(), Some("value".to_string()));
+	assert_eq!(cache::incr("counter", 1, 200).await.unwrap(), 1);
+
+	let guard = cache::try_lock("lock", Duration::from_secs(10))
+		.await
+		.unwrap()
+		.unwrap();
+	assert!(cache::try_lock("lock", Duration::from_secs(10))
+		.await
+		.unwrap()
+		.is_none());
+	guard.release().await.unwrap();
+}
@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+
+use rustgram::service::Service;
+use rustgram::Request;
+use rustgram_server_util::log::{self, log_context_transform, set_level, set_logger, Level, LogEntry, Logger};
+use rustgram_server_util::request_id::{request_id_transform, REQUEST_ID_HEADER};
+use rustgram_server_util::res::{echo, JRes};
+use serde_json::Value;
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CaptureLogger;
+
+impl Logger for CaptureLogger
+{
+	fn log(&self, entry: &LogEntry)
+	{
+		LINES
+			.lock()
+			.unwrap()
+			.push(serde_json::to_string(entry).unwrap());
+	}
+}
+
+fn init()
+{
+	set_logger(Box::new(CaptureLogger));
+	set_level(Level::Info);
+}
+
+fn lines_with(msg: &str) -> Vec<Value>
+{
+	LINES
+		.lock()
+		.unwrap()
+		.iter()
+		.map(|l| serde_json::from_str::<Value>(l).unwrap())
+		.filter(|l| l["msg"] == msg)
+		.collect()
+}
+
+async fn log_handler(_req: Request) -> JRes<&'static str>
+{
+	log::warn("handler log");
+
+	echo("ok")
+}
+
+#[test]
+fn test_log_level()
+{
+	init();
+
+	log::debug("level debug");
+	log::info("level info");
+	log::error("level error");
+
+	assert!(lines_with("level debug").is_empty());
+	assert_eq!(lines_with("level info")[0]["level"], "info");
+	assert_eq!(lines_with("level error")[0]["level"], "error");
+
+	assert!(!log::enabled(Level::Debug));
+	assert!(log::enabled(Level::Warn));
+}
+
+#[tokio::test]
+async fn test_log_request_context()
+{
+	init();
+
+	let service = request_id_transform(log_context_transform(log_handler));
+
+	let req = hyper::Request::builder()
+		.uri("/api/log")
+		.header(REQUEST_ID_HEADER, "log-123")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	service.call(req).await;
+
+	let lines = lines_with("handler log");
+	assert_eq!(lines.len(), 1);
+
+	let line = &lines[0];
+	assert_eq!(line["level"], "warn");
+	assert_eq!(line["request_id"], "log-123");
+	assert_eq!(line["path"], "/api/log");
+	assert!(line["time"].as_u64().unwrap() > 0);
+
+	//no request context outside of the middleware
+	log::warn("no context");
+	assert!(lines_with("no context")[0].get("request_id").is_none());
+}
@@ -1,6 +1,9 @@
 use std::time::Duration;
 
 use rustgram_server_util::cache;
+use rustgram_server_util::cache::{Cache, RateLimitStrategy, RateLimiter};
+use rustgram_server_util::db::id_handling::create_id;
+use rustgram_server_util::res::AppRes;
 
 const KEY: &str = "test_key";
 const VALUE: &str = "test_value";
@@ -26,6 +29,10 @@ async fn tests()
 	println!("store ttl value");
 	store_value_with_ttl().await;
 
+	println!("-----------");
+	println!("touch value");
+	touch_value().await;
+
 	println!("-----------");
 	println!("delete value");
 	delete_value().await;
@@ -33,6 +40,18 @@ async fn tests()
 	println!("-----------");
 	println!("delete multiple value");
 	delete_multiple_test().await;
+
+	println!("-----------");
+	println!("counter");
+	counter_test().await;
+
+	println!("-----------");
+	println!("rate limit");
+	rate_limit_test().await;
+
+	println!("-----------");
+	println!("lock");
+	lock_test().await;
 }
 
 async fn store_value()
@@ -95,3 +114,241 @@ async fn delete_multiple_test()
 		assert_eq!(value, None);
 	}
 }
+
+async fn counter_test()
+{
+	let key = "test_counter";
+
+	assert_eq!(cache::incr(key, 1, 200).await.unwrap(), 1);
+	assert_eq!(cache::incr(key, 5, 200).await.unwrap(), 6);
+	assert_eq!(cache::decr(key, 2, 200).await.unwrap(), 4);
+
+	cache::delete(key).await.unwrap();
+
+	//must start again after delete
+	assert_eq!(cache::incr(key, 1, 200).await.unwrap(), 1);
+
+	cache::delete(key).await.unwrap();
+}
+
+async fn rate_limit_test()
+{
+	//a fixed time in the middle of a window, so the test never crosses a window border.
+	//a new key for each run because the keys of the fixed time outlive the test run
+	let now = 1_700_000_000 - 1_700_000_000 % 3600 + 1800;
+	let key = format!("test_limit_{}", create_id());
+
+	let limiter = RateLimiter::new(3, 3600, RateLimitStrategy::FixedWindow);
+
+	for i in 0..3 {
+		let info = limiter.hit_at(cache::cache(), &key, now).await.unwrap();
+		assert_eq!(info.remaining, 2 - i);
+		assert!(info.allowed);
+	}
+
+	let err = limiter
+		.hit_at(cache::cache(), &key, now + 1)
+		.await
+		.unwrap()
+		.into_result()
+		.unwrap_err();
+	assert_eq!(err.http_status_code, 429);
+
+	//the next window
+	let info = limiter
+		.hit_at(cache::cache(), &key, now + 3600)
+		.await
+		.unwrap();
+	assert!(info.allowed);
+
+	let limiter = RateLimiter::new(2, 3600, RateLimitStrategy::SlidingWindow);
+	let key = format!("test_limit_sliding_{}", create_id());
+
+	limiter.hit_at(cache::cache(), &key, now).await.unwrap();
+	limiter.hit_at(cache::cache(), &key, now).await.unwrap();
+
+	let info = limiter.hit_at(cache::cache(), &key, now).await.unwrap();
+	assert!(!info.allowed);
+	assert_eq!(info.remaining, 0);
+
+	//half of the previous window counts in the middle of the next window
+	let info = limiter
+		.hit_at(cache::cache(), &key, now + 3600)
+		.await
+		.unwrap();
+	assert!(info.allowed);
+
+	let info = limiter
+		.hit_at(cache::cache(), &key, now + 3600)
+		.await
+		.unwrap();
+	assert!(!info.allowed);
+}
+
+#[tokio::test]
+async fn test_rate_limit_first_window()
+{
+	let c = cache::ArrayCache::<String>::new();
+	let limiter = RateLimiter::new(2, 3600, RateLimitStrategy::SlidingWindow);
+
+	//a time in the first window, there is no previous window
+	let info = limiter.hit_at(&c, "key", 10).await.unwrap();
+	assert!(info.allowed);
+	assert_eq!(info.remaining, 1);
+	assert_eq!(info.reset, 3600);
+}
+
+/**
+Needs a running redis, so it only runs with the redis or the tiered cache (CACHE=2 or CACHE=3)
+ */
+#[tokio::test]
+async fn test_tiered_cache_invalidation()
+{
+	dotenv::dotenv().ok();
+
+	let cache = std::env::var("CACHE").unwrap_or_else(|_| "1".to_string());
+
+	if cache != "2" && cache != "3" {
+		return;
+	}
+
+	let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+	let channel = format!("test_tiered_{}", create_id());
+	let key = format!("test_tiered_key_{}", create_id());
+
+	let node_1 = cache::TieredCache::<String>::new(&redis_url, 200, &channel);
+	let node_2 = cache::TieredCache::<String>::new(&redis_url, 200, &channel);
+
+	//wait until both nodes are subscribed
+	tokio::time::sleep(Duration::from_millis(500)).await;
+
+	//add
+	node_1.add(key.clone(), "1".into(), 200).await.unwrap();
+	assert_eq!(node_2.get(&key).await.unwrap(), Some("1".to_string()));
+
+	node_1.add(key.clone(), "2".into(), 200).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(200)).await;
+	assert_eq!(node_2.get(&key).await.unwrap(), Some("2".to_string()));
+
+	//delete
+	node_1.delete(&key).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(200)).await;
+	assert_eq!(node_2.get(&key).await.unwrap(), None);
+
+	//expire
+	node_1.add(key.clone(), "3".into(), 200).await.unwrap();
+	assert_eq!(node_2.get(&key).await.unwrap(), Some("3".to_string()));
+
+	assert!(node_1.expire(&key, 1).await.unwrap());
+	tokio::time::sleep(Duration::from_secs(2)).await;
+	assert_eq!(node_2.get(&key).await.unwrap(), None);
+
+	//the local copy must not outlive the ttl in redis
+	node_1.add(key.clone(), "4".into(), 2).await.unwrap();
+	assert_eq!(node_2.get(&key).await.unwrap(), Some("4".to_string()));
+
+	tokio::time::sleep(Duration::from_secs(3)).await;
+	assert_eq!(node_2.get(&key).await.unwrap(), None);
+}
+
+async fn lock_test()
+{
+	let guard = cache::try_lock("test_lock", Duration::from_secs(10))
+		.await
+		.unwrap()
+		.unwrap();
+
+	//already locked
+	let other = cache::try_lock("test_lock", Duration::from_secs(10))
+		.await
+		.unwrap();
+	assert!(other.is_none());
+
+	let other = cache::lock_with_wait("test_lock", Duration::from_secs(10), Duration::from_millis(200))
+		.await
+		.unwrap();
+	assert!(other.is_none());
+
+	assert!(guard.release().await.unwrap());
+
+	let guard = cache::try_lock("test_lock", Duration::from_secs(10))
+		.await
+		.unwrap()
+		.unwrap();
+
+	//release on drop
+	drop(guard);
+
+	let guard = cache::lock_with_wait("test_lock", Duration::from_secs(10), Duration::from_secs(1))
+		.await
+		.unwrap();
+	assert!(guard.is_some());
+}
+
+async fn touch_value()
+{
+	let ttl = cache::ttl(KEY).await.unwrap().unwrap();
+	assert!(ttl <= Duration::from_secs(200));
+
+	let value = cache::get_and_touch(KEY, 1000).await.unwrap();
+	assert_eq!(value, Some(VALUE.to_string()));
+
+	let ttl = cache::ttl(KEY).await.unwrap().unwrap();
+	assert!(ttl > Duration::from_secs(200));
+
+	assert!(cache::expire(KEY, 200).await.unwrap());
+
+	let ttl = cache::ttl(KEY).await.unwrap().unwrap();
+	assert!(ttl <= Duration::from_secs(200));
+
+	//not existing keys
+	assert_eq!(cache::ttl("not_existing_key").await.unwrap(), None);
+	assert!(!cache::expire("not_existing_key", 200).await.unwrap());
+	assert_eq!(cache::get_and_touch("not_existing_key", 200).await.unwrap(), None);
+}
+
+/**
+A cache like before the counter and ttl methods, it must still build with the defaults
+ */
+struct MinimalCache(cache::ArrayCache<String>);
+
+#[async_trait::async_trait]
+impl Cache<String> for MinimalCache
+{
+	async fn get(&self, key: &str) -> AppRes<Option<String>>
+	{
+		self.0.get(key).await
+	}
+
+	async fn add(&self, key: String, value: String, ttl: usize) -> AppRes<()>
+	{
+		self.0.add(key, value, ttl).await
+	}
+
+	async fn delete(&self, key: &str) -> AppRes<()>
+	{
+		self.0.delete(key).await
+	}
+
+	async fn delete_multiple(&self, keys: &[&str]) -> AppRes<()>
+	{
+		self.0.delete_multiple(keys).await
+	}
+}
+
+#[tokio::test]
+async fn test_cache_defaults()
+{
+	let c = MinimalCache(cache::ArrayCache::new());
+
+	c.add("key".to_string(), VALUE.to_string(), 200)
+		.await
+		.unwrap();
+
+	assert_eq!(c.get_and_touch("key", 300).await.unwrap(), Some(VALUE.to_string()));
+	assert!(c.expire("key", 300).await.unwrap());
+	assert!(!c.expire("other", 300).await.unwrap());
+
+	assert_eq!(c.incr("counter", 1, 200).await.unwrap_err().error_code, 53);
+	assert!(c.ttl("key").await.is_err());
+}
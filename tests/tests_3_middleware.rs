@@ -0,0 +1,72 @@
+use rustgram::service::Service;
+use rustgram::Request;
+use rustgram_server_util::error::{server_err, CoreErrorCodes};
+use rustgram_server_util::request_id::{get_request_id, request_id_transform, REQUEST_ID_HEADER};
+use rustgram_server_util::res::{echo, AppRes, JRes};
+
+mod common;
+
+use common::body_string;
+
+async fn id_handler(req: Request) -> JRes<String>
+{
+	echo(get_request_id(&req).unwrap().to_string())
+}
+
+async fn err_handler(_req: Request) -> AppRes<String>
+{
+	Err(server_err(400, CoreErrorCodes::NoParameter, "No parameter sent"))
+}
+
+#[tokio::test]
+async fn test_request_id()
+{
+	let service = request_id_transform(id_handler);
+
+	//new id
+	let res = service.call(Request::default()).await;
+	let id = res
+		.headers()
+		.get(REQUEST_ID_HEADER)
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.to_string();
+
+	assert!(body_string(res).await.contains(&id));
+
+	//id from the header
+	let req = hyper::Request::builder()
+		.header(REQUEST_ID_HEADER, "abc-123")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+	assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "abc-123");
+
+	//invalid ids are replaced
+	let req = hyper::Request::builder()
+		.header(REQUEST_ID_HEADER, "abc 123")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+	assert_ne!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "abc 123");
+}
+
+#[tokio::test]
+async fn test_request_id_in_error()
+{
+	let service = request_id_transform(err_handler);
+
+	let req = hyper::Request::builder()
+		.header(REQUEST_ID_HEADER, "abc-123")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+
+	assert_eq!(res.status(), 400);
+	assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "abc-123");
+	assert!(body_string(res).await.contains(r#""request_id":"abc-123""#));
+}
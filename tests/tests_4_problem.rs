@@ -0,0 +1,54 @@
+use rustgram::service::Service;
+use rustgram::Request;
+use rustgram_server_util::error::{server_err, CoreErrorCodes};
+use rustgram_server_util::problem::{problem_negotiation_transform, set_error_format, set_problem_type_mapper, ErrorFormat, PROBLEM_CONTENT_TYPE};
+use rustgram_server_util::res::AppRes;
+
+async fn err_handler(_req: Request) -> AppRes<String>
+{
+	Err(server_err(400, CoreErrorCodes::NoParameter, "No parameter sent"))
+}
+
+#[tokio::test]
+async fn test_problem_negotiation()
+{
+	//the format is global, so this is the only test in this file
+	set_error_format(ErrorFormat::Negotiate);
+	set_problem_type_mapper(Box::new(|code| Some(format!("https://example.com/errors/{}", code))));
+
+	let service = problem_negotiation_transform(err_handler);
+
+	let req = hyper::Request::builder()
+		.header("Accept", PROBLEM_CONTENT_TYPE)
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+
+	assert_eq!(res.status(), 400);
+	assert_eq!(res.headers().get("Content-Type").unwrap(), PROBLEM_CONTENT_TYPE);
+
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+	assert_eq!(body["type"], "https://example.com/errors/40");
+	assert_eq!(body["title"], "Bad Request");
+	assert_eq!(body["status"], 400);
+	assert_eq!(body["detail"], "No parameter sent");
+	assert_eq!(body["err_code"], 40);
+
+	//without the accept header
+	let res = service.call(Request::default()).await;
+
+	assert_eq!(res.headers().get("Content-Type").unwrap(), "application/json");
+
+	//not acceptable
+	let req = hyper::Request::builder()
+		.header("Accept", "application/problem+json;q=0, application/json")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+
+	assert_eq!(res.headers().get("Content-Type").unwrap(), "application/json");
+}
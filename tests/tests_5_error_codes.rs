@@ -0,0 +1,103 @@
+use std::error::Error;
+
+use rustgram_server_util::error::{CoreErrorCodes, ErrorCodeCatalog, ErrorRegistry, ResultContext, ServerCoreError, ServerErrorCodes};
+use rustgram_server_util_macros::ServerErrorCodes;
+
+#[derive(ServerErrorCodes)]
+pub enum AppErrorCodes
+{
+	#[server_error(code = 1000, status = 400, msg = "User not found")]
+	UserNotFound,
+	#[server_error(code = 1001)]
+	UserExists,
+}
+
+#[derive(ServerErrorCodes)]
+pub enum WrongErrorCodes
+{
+	#[server_error(code = 1001)]
+	Wrong,
+	//core code
+	#[server_error(code = 21, status = 422)]
+	Db,
+}
+
+#[test]
+fn test_derive()
+{
+	assert_eq!(AppErrorCodes::UserNotFound.get_int_code(), 1000);
+	assert_eq!(AppErrorCodes::UserExists.get_int_code(), 1001);
+
+	let catalog = AppErrorCodes::catalog();
+
+	assert_eq!(catalog.len(), 2);
+	assert_eq!(catalog[0].name, "UserNotFound");
+	assert_eq!(catalog[0].status, Some(400));
+	assert_eq!(catalog[0].msg, Some("User not found"));
+	assert_eq!(catalog[1].status, None);
+}
+
+#[test]
+fn test_registry()
+{
+	//core codes must be unique
+	ErrorRegistry::new().check().unwrap();
+
+	let registry = ErrorRegistry::new().register::<AppErrorCodes>();
+	registry.check().unwrap();
+
+	let md = registry.to_markdown();
+	assert!(md.contains("| 1000 | UserNotFound | 400 | User not found |"));
+
+	let json = registry.to_json().unwrap();
+	assert!(json.contains(r#""code":1000"#));
+
+	let registry = registry.register::<WrongErrorCodes>();
+
+	let collisions = registry.collisions();
+	assert_eq!(collisions.len(), 2);
+	assert!(registry.check().is_err());
+}
+
+#[test]
+fn test_context()
+{
+	let res: Result<(), std::io::Error> = Err(std::io::Error::new(std::io::ErrorKind::NotFound, "file not found"));
+
+	let err = res
+		.context(400, CoreErrorCodes::FileLocalOpen, "Can't open the file")
+		.unwrap_err();
+
+	assert_eq!(err.http_status_code, 400);
+	assert_eq!(err.error_code, 500);
+	assert_eq!(err.msg, "Can't open the file");
+	assert_eq!(err.debug_msg.as_deref(), Some("file not found"));
+	assert!(err.source().is_some());
+
+	//chain with another context
+	let res: Result<(), ServerCoreError> = Err(err);
+
+	let err = res
+		.context(500, AppErrorCodes::UserExists, "User error")
+		.unwrap_err();
+
+	assert_eq!(err.error_code, 1001);
+	assert_eq!(
+		err.debug_msg.as_deref(),
+		Some("Core error. Code: 500, Message: Can't open the file: caused by: file not found")
+	);
+
+	let err: ServerCoreError = serde_json::from_str::<u32>("abc").unwrap_err().into();
+	assert_eq!(err.error_code, 11);
+	assert_eq!(err.http_status_code, 422);
+
+	let err: ServerCoreError = serde_json::from_str::<u32>("-1").unwrap_err().into();
+	assert_eq!(err.http_status_code, 422);
+
+	//map keys must be strings, a bug of the server
+	let map = std::collections::HashMap::from([(vec![1], 1)]);
+
+	let err: ServerCoreError = serde_json::to_string(&map).unwrap_err().into();
+	assert_eq!(err.error_code, 10);
+	assert_eq!(err.http_status_code, 500);
+}
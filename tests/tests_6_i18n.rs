@@ -0,0 +1,83 @@
+use rustgram::service::Service;
+use rustgram::Request;
+use rustgram_server_util::error::{server_err, server_err_owned, CoreErrorCodes};
+use rustgram_server_util::i18n::{locale_transform, parse_accept_language, set_catalog, MessageCatalog};
+use rustgram_server_util::res::AppRes;
+
+mod common;
+
+use common::body_string;
+
+async fn err_handler(_req: Request) -> AppRes<String>
+{
+	Err(server_err(
+		413,
+		CoreErrorCodes::InputTooBig,
+		"Input was too big to handle",
+	))
+}
+
+async fn err_param_handler(_req: Request) -> AppRes<String>
+{
+	Err(server_err_owned(
+		400,
+		CoreErrorCodes::FileSave,
+		"File chunk is too large".to_string(),
+		None,
+	)
+	.with_param("max_chunk_size", 100))
+}
+
+#[test]
+fn test_parse_accept_language()
+{
+	assert_eq!(
+		parse_accept_language("en;q=0.5, de-AT, fr;q=0.8, *;q=0.1"),
+		vec!["de-at".to_string(), "fr".to_string(), "en".to_string()]
+	);
+
+	assert_eq!(parse_accept_language("de;q=0, en;q=0.5"), vec!["en".to_string()]);
+}
+
+#[tokio::test]
+async fn test_translated_error()
+{
+	let mut catalog = MessageCatalog::new();
+	catalog.add("de", 12, "Die Eingabe ist zu groß".to_string());
+	catalog.add("de", 502, "Die maximale Größe ist: {max_chunk_size}".to_string());
+
+	set_catalog(catalog);
+
+	let service = locale_transform(err_handler);
+
+	let req = hyper::Request::builder()
+		.header("Accept-Language", "de-AT, en;q=0.5")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+	assert!(body_string(res).await.contains("Die Eingabe ist zu groß"));
+
+	//fallback to the error msg
+	let req = hyper::Request::builder()
+		.header("Accept-Language", "fr")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+	assert!(body_string(res)
+		.await
+		.contains("Input was too big to handle"));
+
+	let service = locale_transform(err_param_handler);
+
+	let req = hyper::Request::builder()
+		.header("Accept-Language", "de")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let res = service.call(req).await;
+	assert!(body_string(res)
+		.await
+		.contains("Die maximale Größe ist: 100"));
+}
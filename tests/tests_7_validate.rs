@@ -0,0 +1,98 @@
+use rustgram::service::IntoResponse;
+use rustgram_server_util::db::id_handling::create_id;
+use rustgram_server_util::input_helper::{bytes_to_json, bytes_to_validated_json};
+use rustgram_server_util::res::AppRes;
+use rustgram_server_util::validate::Validate;
+use rustgram_server_util_macros::Validate;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RegisterInput
+{
+	#[validate(length(min = 3, max = 20), regex = "^[a-z0-9_]+$")]
+	name: String,
+	#[validate(email)]
+	email: String,
+	#[validate(range(min = 13, max = 150))]
+	age: Option<u32>,
+	#[validate(uuid)]
+	group_id: String,
+}
+
+fn register() -> AppRes<RegisterInput>
+{
+	bytes_to_validated_json(br#"{"name": "a", "email": "abc", "age": 5, "group_id": "123"}"#)
+}
+
+#[test]
+fn test_validate()
+{
+	let input = RegisterInput {
+		name: "user_1".to_string(),
+		email: "user@example.com".to_string(),
+		age: None,
+		group_id: create_id(),
+	};
+
+	input.validate().unwrap();
+
+	let input = RegisterInput {
+		name: "User 1".to_string(),
+		email: "user@example".to_string(),
+		age: Some(200),
+		group_id: "abc".to_string(),
+	};
+
+	let err = input.validate().unwrap_err();
+
+	assert_eq!(err.http_status_code, 422);
+	assert_eq!(err.error_code, 15);
+
+	let codes: Vec<(&str, &str)> = err
+		.errors
+		.iter()
+		.map(|e| (e.field.as_str(), e.code))
+		.collect();
+
+	assert_eq!(
+		codes,
+		vec![("name", "regex"), ("email", "email"), ("age", "range"), ("group_id", "uuid")]
+	);
+}
+
+#[test]
+fn test_json_err_without_internals()
+{
+	let err = bytes_to_json::<RegisterInput>(br#"{"name": "user_1"}"#).unwrap_err();
+
+	assert_eq!(err.msg, "Wrong input");
+	assert_eq!(err.errors[0].message, "missing field `email` at line 1 column 18");
+
+	//no made up field for errors of the whole body
+	let error = serde_json::to_value(&err.errors[0]).unwrap();
+
+	assert_eq!(
+		error,
+		serde_json::json!({"code": "json", "message": "missing field `email` at line 1 column 18"})
+	);
+}
+
+#[tokio::test]
+async fn test_errors_in_response()
+{
+	let res = register().unwrap_err().into_response();
+
+	assert_eq!(res.status(), 422);
+
+	let body = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+	assert_eq!(body["err_code"], 15);
+
+	let errors = body["errors"].as_array().unwrap();
+
+	assert_eq!(errors.len(), 4);
+	assert_eq!(errors[0]["field"], "name");
+	assert_eq!(errors[0]["code"], "length");
+	assert_eq!(errors[0]["message"], "Must be at least 3 long");
+}
@@ -0,0 +1,306 @@
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder, ZlibEncoder};
+use rustgram::Request;
+use rustgram_server_util::error::{server_err, CoreErrorCodes, ServerCoreError};
+use rustgram_server_util::file::{FileHandler, LocalStorage};
+use rustgram_server_util::input_helper::{get_json_body, get_json_body_with_limit, get_multipart, get_raw_body_with_limit, MultipartLimits};
+use rustgram_server_util::value::Value;
+use serde::Deserialize;
+use tokio::io::AsyncReadExt;
+
+#[derive(Deserialize)]
+struct Input
+{
+	name: String,
+}
+
+fn json_req(body: &'static str) -> Request
+{
+	hyper::Request::builder()
+		.body(hyper::Body::from(body))
+		.unwrap()
+}
+
+#[tokio::test]
+async fn test_json_body()
+{
+	let mut req = json_req(r#"{"name": "abc"}"#);
+
+	let input: Input = get_json_body(&mut req).await.unwrap();
+	assert_eq!(input.name, "abc");
+
+	//limit for the stream without content length
+	let mut req = json_req(r#"{"name": "abc"}"#);
+
+	let err = get_json_body_with_limit::<Input>(&mut req, 5)
+		.await
+		.err()
+		.unwrap();
+	assert_eq!(err.http_status_code, 413);
+}
+
+#[tokio::test]
+async fn test_content_length_reject()
+{
+	let mut req = hyper::Request::builder()
+		.header("Content-Length", "1000")
+		.body(hyper::Body::empty())
+		.unwrap();
+
+	let err = get_raw_body_with_limit(&mut req, 100).await.unwrap_err();
+	assert_eq!(err.http_status_code, 413);
+	assert_eq!(err.error_code, 12);
+}
+
+#[tokio::test]
+async fn test_stream_err()
+{
+	let (mut sender, body) = hyper::Body::channel();
+
+	sender
+		.send_data(hyper::body::Bytes::from_static(br#"{"name": "#))
+		.await
+		.unwrap();
+	sender.abort();
+
+	let mut req = hyper::Request::builder().body(body).unwrap();
+
+	let err = get_raw_body_with_limit(&mut req, 100).await.unwrap_err();
+	assert_eq!(err.http_status_code, 400);
+	assert_eq!(err.error_code, 16);
+}
+
+fn multipart_req(body: &'static str) -> Request
+{
+	hyper::Request::builder()
+		.header("Content-Type", "multipart/form-data; boundary=X-BOUNDARY")
+		.body(hyper::Body::from(body.replace('\n', "\r\n")))
+		.unwrap()
+}
+
+const MULTIPART_BODY: &str = "--X-BOUNDARY
+Content-Disposition: form-data; name=\"name\"
+
+abc
+--X-BOUNDARY
+Content-Disposition: form-data; name=\"tag\"
+
+a
+--X-BOUNDARY
+Content-Disposition: form-data; name=\"tag\"
+
+b
+--X-BOUNDARY
+Content-Disposition: form-data; name=\"file\"; filename=\"hello.txt\"
+Content-Type: text/plain
+
+hello world
+--X-BOUNDARY--
+";
+
+#[tokio::test]
+async fn test_multipart()
+{
+	let dir = std::env::temp_dir().join("rustgram_multipart_test");
+	std::fs::create_dir_all(&dir).unwrap();
+
+	let storage = LocalStorage::new(dir.to_str().unwrap().to_string());
+
+	let data = get_multipart(multipart_req(MULTIPART_BODY), &storage, &MultipartLimits::default())
+		.await
+		.unwrap();
+
+	let Value::Object(fields) = data.fields else {
+		panic!("fields must be an object");
+	};
+
+	assert!(matches!(fields.get("name"), Some(Value::String(s)) if s == "abc"));
+	assert!(matches!(fields.get("tag"), Some(Value::Array(a)) if a.len() == 2));
+
+	assert_eq!(data.files.len(), 1);
+
+	let file = &data.files[0];
+	assert_eq!(file.file_name.as_deref(), Some("hello.txt"));
+	assert_eq!(file.content_type.as_deref(), Some("text/plain"));
+	assert_eq!(file.size, 11);
+	assert_eq!(
+		std::fs::read_to_string(dir.join(&file.part_id)).unwrap(),
+		"hello world"
+	);
+
+	storage.delete_part(&file.part_id).await.unwrap();
+
+	//file too large
+	let limits = MultipartLimits {
+		max_file_size: 5,
+		..Default::default()
+	};
+
+	let err = get_multipart(multipart_req(MULTIPART_BODY), &storage, &limits)
+		.await
+		.err()
+		.unwrap();
+	assert_eq!(err.error_code, 502);
+
+	//field too large
+	let limits = MultipartLimits {
+		max_field_size: 2,
+		..Default::default()
+	};
+
+	let err = get_multipart(multipart_req(MULTIPART_BODY), &storage, &limits)
+		.await
+		.err()
+		.unwrap();
+	assert_eq!(err.http_status_code, 413);
+}
+
+async fn compress(encoding: &str, data: &[u8]) -> Vec<u8>
+{
+	let mut out = Vec::new();
+
+	match encoding {
+		"gzip" => GzipEncoder::new(data).read_to_end(&mut out).await.unwrap(),
+		"deflate" => ZlibEncoder::new(data).read_to_end(&mut out).await.unwrap(),
+		_ => {
+			BrotliEncoder::new(data)
+				.read_to_end(&mut out)
+				.await
+				.unwrap()
+		},
+	};
+
+	out
+}
+
+fn compressed_req(encoding: &str, body: Vec<u8>) -> Request
+{
+	hyper::Request::builder()
+		.header("Content-Encoding", encoding)
+		.body(hyper::Body::from(body))
+		.unwrap()
+}
+
+#[tokio::test]
+async fn test_decompression()
+{
+	for encoding in ["gzip", "deflate", "br"] {
+		let mut req = compressed_req(encoding, compress(encoding, br#"{"name": "abc"}"#).await);
+
+		let input: Input = get_json_body(&mut req).await.unwrap();
+		assert_eq!(input.name, "abc");
+	}
+
+	//compression bomb
+	let mut req = compressed_req("gzip", compress("gzip", &vec![0; 1_000_000]).await);
+
+	let err = get_raw_body_with_limit(&mut req, 10_000).await.unwrap_err();
+	assert_eq!(err.http_status_code, 413);
+
+	let mut req = compressed_req("zstd", Vec::new());
+
+	let err = get_raw_body_with_limit(&mut req, 10_000).await.unwrap_err();
+	assert_eq!(err.http_status_code, 415);
+}
+
+const MULTIPART_TWO_FILES: &str = "--X-BOUNDARY
+Content-Disposition: form-data; name=\"small\"; filename=\"small.txt\"
+
+small
+--X-BOUNDARY
+Content-Disposition: form-data; name=\"large\"; filename=\"large.txt\"
+
+hello world
+--X-BOUNDARY--
+";
+
+#[tokio::test]
+async fn test_multipart_cleanup()
+{
+	//own dir to check that no file is left
+	let dir = std::env::temp_dir().join("rustgram_multipart_cleanup_test");
+	let _ = std::fs::remove_dir_all(&dir);
+	std::fs::create_dir_all(&dir).unwrap();
+
+	let storage = LocalStorage::new(dir.to_str().unwrap().to_string());
+
+	//the first file fits, the second is too large
+	let limits = MultipartLimits {
+		max_file_size: 8,
+		..Default::default()
+	};
+
+	let err = get_multipart(multipart_req(MULTIPART_TWO_FILES), &storage, &limits)
+		.await
+		.err()
+		.unwrap();
+	assert_eq!(err.error_code, 502);
+
+	//the already written first file must be removed
+	assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+}
+
+/**
+A handler which leaves every failed part behind and can't delete, like an external storage which is not reachable anymore
+ */
+#[derive(Default)]
+struct FailingStorage
+{
+	uploaded: std::sync::Mutex<Vec<String>>,
+	deleted: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait::async_trait]
+impl FileHandler for FailingStorage
+{
+	async fn get_part(&self, _part_id: &str, _content_type: Option<&str>) -> Result<rustgram::Response, ServerCoreError>
+	{
+		Err(server_err(404, CoreErrorCodes::FileDownload, "not found"))
+	}
+
+	async fn upload_part(&self, _req: Request, part_id: &str, _max_chunk_size: usize) -> Result<usize, ServerCoreError>
+	{
+		let mut uploaded = self.uploaded.lock().unwrap();
+		uploaded.push(part_id.to_string());
+
+		//the second part fails in the middle
+		if uploaded.len() > 1 {
+			return Err(server_err(400, CoreErrorCodes::FileSave, "upload failed"));
+		}
+
+		Ok(5)
+	}
+
+	async fn delete_part(&self, part_id: &str) -> Result<(), ServerCoreError>
+	{
+		self.delete_parts(&[part_id.to_string()]).await
+	}
+
+	async fn delete_parts(&self, parts: &[String]) -> Result<(), ServerCoreError>
+	{
+		self.deleted.lock().unwrap().extend_from_slice(parts);
+
+		Err(server_err(500, CoreErrorCodes::FileRemove, "delete failed"))
+	}
+}
+
+#[tokio::test]
+async fn test_multipart_cleanup_failed_part()
+{
+	let storage = FailingStorage::default();
+
+	let err = get_multipart(
+		multipart_req(MULTIPART_TWO_FILES),
+		&storage,
+		&MultipartLimits::default(),
+	)
+	.await
+	.err()
+	.unwrap();
+
+	//the upload error and not the one of the cleanup
+	assert_eq!(err.http_status_code, 400);
+
+	//the failed part must be deleted too
+	assert_eq!(*storage.deleted.lock().unwrap(), *storage.uploaded.lock().unwrap());
+	assert_eq!(storage.deleted.lock().unwrap().len(), 2);
+}
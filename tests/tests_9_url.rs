@@ -0,0 +1,139 @@
+use rustgram::{Request, RouteParams};
+use rustgram_server_util::db::id_handling::create_id;
+use rustgram_server_util::url_helper::{get_id_param, get_number_param, get_param, get_params_as, get_query};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct ListQuery
+{
+	#[serde(default)]
+	page: u32,
+	search: Option<String>,
+	#[serde(default)]
+	ids: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+struct RequiredQuery
+{
+	id: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[allow(dead_code)]
+struct IdsQuery
+{
+	ids: Vec<u32>,
+}
+
+fn req(uri: &str) -> Request
+{
+	hyper::Request::builder()
+		.uri(uri)
+		.body(hyper::Body::empty())
+		.unwrap()
+}
+
+#[test]
+fn test_query()
+{
+	let query: ListQuery = get_query(&req("/list?page=2&search=a%20b%3Dc&ids[0]=x&ids[1]=y")).unwrap();
+
+	assert_eq!(query.page, 2);
+	assert_eq!(query.search.as_deref(), Some("a b=c"));
+	assert_eq!(query.ids, vec!["x".to_string(), "y".to_string()]);
+
+	//percent-encoded brackets
+	let query: ListQuery = get_query(&req("/list?ids%5B0%5D=x")).unwrap();
+	assert_eq!(query.ids, vec!["x".to_string()]);
+
+	//no query
+	let query: ListQuery = get_query(&req("/list")).unwrap();
+	assert_eq!(query.page, 0);
+	assert!(query.search.is_none());
+}
+
+#[test]
+fn test_query_err()
+{
+	let err = get_query::<RequiredQuery>(&req("/list?page=1")).unwrap_err();
+
+	assert_eq!(err.http_status_code, 400);
+	assert_eq!(err.error_code, 42);
+	assert_eq!(err.errors[0].field, "id");
+
+	let err = get_query::<ListQuery>(&req("/list?page=abc")).unwrap_err();
+	assert_eq!(err.http_status_code, 400);
+	assert_eq!(err.errors[0].field, "page");
+
+	let err = get_query::<IdsQuery>(&req("/list?ids[0]=x")).unwrap_err();
+	assert_eq!(err.errors[0].field, "ids[0]");
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Order
+{
+	Asc,
+	Desc,
+}
+
+#[derive(Deserialize, Debug)]
+struct PostParams
+{
+	user_id: String,
+	page: u32,
+	order: Option<Order>,
+}
+
+fn route_req(route: &str, path: &str) -> Request
+{
+	let mut router = matchit::Router::new();
+	router.insert(route, ()).unwrap();
+
+	let params: RouteParams = router.at(path).unwrap().params.into();
+
+	let mut req = req(path);
+	req.extensions_mut().insert(params);
+
+	req
+}
+
+#[test]
+fn test_params()
+{
+	let id = create_id();
+	let req = route_req(
+		"/user/:user_id/post/:page/:order",
+		&format!("/user/{}/post/2/desc", id),
+	);
+
+	let page: u32 = get_param(&req, "page").unwrap();
+	assert_eq!(page, 2);
+
+	let err = get_param::<bool>(&req, "page").unwrap_err();
+	assert_eq!(err.error_code, 43);
+	assert_eq!(err.errors[0].field, "page");
+
+	assert_eq!(get_id_param(&req, "user_id").unwrap(), id);
+	assert_eq!(get_id_param(&req, "page").unwrap_err().error_code, 1);
+
+	assert_eq!(get_number_param(&req, "page", 1, 10).unwrap(), 2);
+	assert_eq!(
+		get_number_param(&req, "page", 5, 10)
+			.unwrap_err()
+			.error_code,
+		44
+	);
+
+	let params: PostParams = get_params_as(&req).unwrap();
+	assert_eq!(params.user_id, id);
+	assert_eq!(params.page, 2);
+	assert_eq!(params.order, Some(Order::Desc));
+
+	let req = route_req("/user/:user_id/post/:page", "/user/abc/post/abc");
+
+	let err = get_params_as::<PostParams>(&req).unwrap_err();
+	assert_eq!(err.error_code, 43);
+}